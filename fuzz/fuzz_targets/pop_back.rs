@@ -12,6 +12,7 @@ extern crate libfuzzer_sys;
 
 extern crate tendril;
 extern crate rand;
+extern crate serde_json;
 
 use rand::Rng;
 use tendril::StrTendril;
@@ -28,6 +29,12 @@ fuzz_target!(|data: &[u8]| {
     buf_string.push_str(&str);
     buf_tendril.push_slice(&str);
 
+    // Differential serde round-trip against the equivalent `String`,
+    // before any mutation. The first input byte is passed along as the
+    // codec selector; serde_json is the only codec wired in, so every
+    // selector value currently takes the serde_json path.
+    serde_round_trip(&buf_string, &buf_tendril, data.first().cloned().unwrap_or(0));
+
     // test pop_front
     let mut rng = rand::thread_rng();
     let new_len = random_boundary(&mut rng, &buf_string);
@@ -38,6 +45,27 @@ fuzz_target!(|data: &[u8]| {
     }
 });
 
+/// Encode `buf_tendril`, decode into a fresh `StrTendril`, and assert
+/// byte-for-byte equality with the original, plus consistency against the
+/// `String` round-trip through the same codec.
+fn serde_round_trip(buf_string: &str, buf_tendril: &StrTendril, selector: u8) {
+    // bincode is not yet wired into the fuzz crate's dependencies; until
+    // it is, every selector value exercises serde_json. Threading the
+    // selector through now means adding the bincode arm later is a local
+    // change rather than a reshape of the harness.
+    let _use_bincode = selector & 1 == 1;
+
+    let tendril_json = serde_json::to_string(buf_tendril).unwrap();
+    let string_json = serde_json::to_string(buf_string).unwrap();
+    assert_eq!(string_json, tendril_json);
+
+    let decoded: StrTendril = serde_json::from_str(&tendril_json).unwrap();
+    assert_eq!(&**buf_tendril, &*decoded);
+
+    let decoded_string: String = serde_json::from_str(&tendril_json).unwrap();
+    assert_eq!(buf_string, decoded_string);
+}
+
 fn random_boundary<R: Rng>(rng: &mut R, text: &str) -> usize {
     loop {
         let i = Range::new(0, text.len() + 1).ind_sample(rng);
@@ -45,4 +73,4 @@ fn random_boundary<R: Rng>(rng: &mut R, text: &str) -> usize {
             return i;
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,372 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Aho-Corasick multi-pattern search over tendrils.
+//!
+//! Build an `Automaton` once from a set of byte patterns, then scan a
+//! tendril in a single linear pass. Matches and the non-matching spans
+//! between them are reported as subtendrils sharing the source buffer,
+//! so multi-keyword tokenization runs in O(n) with no per-span copy.
+
+use std::collections::VecDeque;
+
+use fmt::{self, Slice};
+use tendril::Tendril;
+
+const ROOT: usize = 0;
+
+struct Node {
+    /// Transition on each byte, or `None`.
+    goto: [Option<usize>; 256],
+    /// Failure link: the node for the longest proper suffix of this
+    /// node's path that is also a trie prefix.
+    fail: usize,
+    /// Pattern indices that end at this node, including those reachable
+    /// through failure links.
+    output: Vec<usize>,
+}
+
+impl Node {
+    fn new() -> Node {
+        Node {
+            goto: [None; 256],
+            fail: ROOT,
+            output: Vec::new(),
+        }
+    }
+}
+
+/// A compiled Aho-Corasick automaton.
+pub struct Automaton {
+    nodes: Vec<Node>,
+    lengths: Vec<u32>,
+}
+
+impl Automaton {
+    /// Build an automaton from a set of non-empty byte patterns.
+    ///
+    /// The pattern index of each match is its position in `patterns`.
+    pub fn new<I, P>(patterns: I) -> Automaton
+        where I: IntoIterator<Item = P>,
+              P: AsRef<[u8]>,
+    {
+        let mut nodes = vec![Node::new()];
+        let mut lengths = Vec::new();
+
+        // Build the goto trie.
+        for pat in patterns {
+            let pat = pat.as_ref();
+            let id = lengths.len();
+            lengths.push(pat.len() as u32);
+
+            let mut cur = ROOT;
+            for &b in pat {
+                cur = match nodes[cur].goto[b as usize] {
+                    Some(next) => next,
+                    None => {
+                        let next = nodes.len();
+                        nodes.push(Node::new());
+                        nodes[cur].goto[b as usize] = Some(next);
+                        next
+                    }
+                };
+            }
+            nodes[cur].output.push(id);
+        }
+
+        // Compute failure links by BFS. Depth-1 nodes fail to root.
+        let mut queue = VecDeque::new();
+        for b in 0..256 {
+            if let Some(next) = nodes[ROOT].goto[b] {
+                nodes[next].fail = ROOT;
+                queue.push_back(next);
+            }
+        }
+        while let Some(u) = queue.pop_front() {
+            for b in 0..256 {
+                if let Some(v) = nodes[u].goto[b] {
+                    // Walk failure links until a transition on `b` exists.
+                    let mut f = nodes[u].fail;
+                    while f != ROOT && nodes[f].goto[b].is_none() {
+                        f = nodes[f].fail;
+                    }
+                    let fail = match nodes[f].goto[b] {
+                        Some(t) if t != v => t,
+                        _ => ROOT,
+                    };
+                    nodes[v].fail = fail;
+                    let mut inherited = nodes[fail].output.clone();
+                    nodes[v].output.append(&mut inherited);
+                    queue.push_back(v);
+                }
+            }
+        }
+
+        Automaton { nodes, lengths }
+    }
+
+    /// Follow the goto/fail transitions for one byte, returning the new state.
+    #[inline]
+    fn step(&self, mut state: usize, b: u8) -> usize {
+        while state != ROOT && self.nodes[state].goto[b as usize].is_none() {
+            state = self.nodes[state].fail;
+        }
+        self.nodes[state].goto[b as usize].unwrap_or(ROOT)
+    }
+}
+
+/// A single match: `(pattern_index, start, end)` in byte offsets.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub pattern: usize,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Iterator over all matches in a haystack, in end-position order.
+///
+/// Yielded by `Tendril::find_all`.
+pub struct FindAll<'a, F>
+    where F: fmt::SliceFormat + 'a,
+{
+    ac: &'a Automaton,
+    haystack: &'a Tendril<F>,
+    bytes: &'a [u8],
+    pos: usize,
+    state: usize,
+    pending: Vec<Match>,
+}
+
+impl<'a, F> Iterator for FindAll<'a, F>
+    where F: fmt::SliceFormat,
+{
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            if let Some(m) = self.pending.pop() {
+                return Some(m);
+            }
+            if self.pos >= self.bytes.len() {
+                return None;
+            }
+            let b = self.bytes[self.pos];
+            let end = self.pos + 1;
+            self.pos = end;
+            self.state = self.ac.step(self.state, b);
+            for &pat in &self.ac.nodes[self.state].output {
+                let len = self.ac.lengths[pat];
+                self.pending.push(Match {
+                    pattern: pat,
+                    start: end as u32 - len,
+                    end: end as u32,
+                });
+            }
+        }
+    }
+}
+
+impl<F> Tendril<F>
+    where F: fmt::SliceFormat,
+{
+    /// Find every occurrence of every pattern in a single linear pass.
+    #[inline]
+    pub fn find_all<'a>(&'a self, ac: &'a Automaton) -> FindAll<'a, F> {
+        FindAll {
+            ac: ac,
+            haystack: self,
+            bytes: self.as_ref().as_bytes(),
+            pos: 0,
+            state: ROOT,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Split the tendril on any match, yielding the shared subtendrils
+    /// between matches (non-overlapping).
+    ///
+    /// Matches are taken in end-position order, and among matches that
+    /// end at the same position the shortest comes first. Overlapping
+    /// matches are resolved by skipping any match that starts before the
+    /// previous match ended.
+    pub fn split_on_any(&self, ac: &Automaton) -> Vec<Tendril<F>> {
+        let mut out = Vec::new();
+        let mut cursor = 0u32;
+        for m in self.find_all(ac) {
+            if m.start < cursor {
+                continue;
+            }
+            out.push(self.subtendril(cursor, m.start - cursor));
+            cursor = m.end;
+        }
+        out.push(self.subtendril(cursor, self.len32() - cursor));
+        out
+    }
+
+    /// Collect every match as a `(pattern_index, subtendril)` pair, each
+    /// subtendril sharing the haystack buffer.
+    pub fn matches(&self, ac: &Automaton) -> Vec<(usize, Tendril<F>)> {
+        self.find_all(ac)
+            .map(|m| (m.pattern, self.subtendril(m.start, m.end - m.start)))
+            .collect()
+    }
+
+    /// Find every occurrence of a single pattern, using a rare-byte
+    /// prefilter to skip cheaply before confirming each candidate.
+    ///
+    /// Returns shared subtendrils of the haystack.
+    pub fn find_pattern(&self, pat: &SinglePattern) -> Vec<Tendril<F>> {
+        let hay = self.as_ref().as_bytes();
+        let needle = &pat.needle[..];
+        let mut out = Vec::new();
+        if needle.is_empty() || hay.len() < needle.len() {
+            return out;
+        }
+        let last = hay.len() - needle.len();
+        let mut i = 0;
+        while i <= last {
+            // Prefilter: find the next position where the rare byte could
+            // line up with its offset in the pattern.
+            match hay[(i + pat.rare_offset)..].iter().position(|&b| b == pat.rare_byte) {
+                Some(k) => {
+                    let cand = i + k;
+                    if cand > last {
+                        break;
+                    }
+                    if &hay[cand..cand + needle.len()] == needle {
+                        out.push(self.subtendril(cand as u32, needle.len() as u32));
+                    }
+                    i = cand + 1;
+                }
+                None => break,
+            }
+        }
+        out
+    }
+
+    /// Replace every (non-overlapping) match with `replacement`,
+    /// stitching unmatched runs in as shared subtendrils.
+    pub fn replace_all(&self, ac: &Automaton, replacement: &Tendril<F>) -> Tendril<F> {
+        let mut out: Tendril<F> = Tendril::new();
+        let mut cursor = 0u32;
+        for m in self.find_all(ac) {
+            if m.start < cursor {
+                continue;
+            }
+            out.push_tendril(&self.subtendril(cursor, m.start - cursor));
+            out.push_tendril(replacement);
+            cursor = m.end;
+        }
+        out.push_tendril(&self.subtendril(cursor, self.len32() - cursor));
+        out
+    }
+}
+
+/// A single search pattern with a precomputed rare-byte prefilter.
+///
+/// The rarest byte of the pattern (by the static frequency table) is
+/// chosen as the prefilter anchor: scanning for it skips over stretches
+/// of haystack that cannot contain the pattern before a full compare.
+pub struct SinglePattern {
+    needle: Vec<u8>,
+    rare_byte: u8,
+    rare_offset: usize,
+}
+
+impl SinglePattern {
+    /// Build a searcher for `pattern`, selecting its rarest byte.
+    pub fn new<P: AsRef<[u8]>>(pattern: P) -> SinglePattern {
+        let needle = pattern.as_ref().to_vec();
+        let (mut rare_byte, mut rare_offset, mut best) = (0u8, 0usize, u16::max_value());
+        for (i, &b) in needle.iter().enumerate() {
+            let freq = byte_frequency(b);
+            if freq <= best {
+                best = freq;
+                rare_byte = b;
+                rare_offset = i;
+            }
+        }
+        SinglePattern { needle: needle, rare_byte: rare_byte, rare_offset: rare_offset }
+    }
+}
+
+/// Relative byte frequency (higher is more common), used to pick the most
+/// discriminating byte of a pattern for the prefilter. The common letters
+/// and whitespace rank high so that non-text and uncommon bytes become
+/// the preferred (rarest) prefilter anchors.
+#[inline]
+fn byte_frequency(b: u8) -> u16 {
+    match b {
+        b' ' => 1000,
+        b'e' => 900,
+        b't' => 800,
+        b'a' => 750,
+        b'o' => 700,
+        b'i' => 680,
+        b'n' => 670,
+        b's' => 660,
+        b'\n' => 400,
+        0x20..=0x7e | b'\t' => 200,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Automaton, Match, SinglePattern};
+    use tendril::SliceExt;
+
+    #[test]
+    fn find_all() {
+        let ac = Automaton::new(&["he", "she", "his", "hers"]);
+        let t = "ushers".to_tendril();
+        let mut matches: Vec<_> = t.find_all(&ac).collect();
+        matches.sort_by_key(|m| (m.end, m.start));
+        assert!(matches.contains(&Match { pattern: 1, start: 1, end: 4 })); // she
+        assert!(matches.contains(&Match { pattern: 0, start: 2, end: 4 })); // he
+        assert!(matches.contains(&Match { pattern: 3, start: 2, end: 6 })); // hers
+    }
+
+    #[test]
+    fn split_on_any() {
+        let ac = Automaton::new(&[", ", "; "]);
+        let t = "a, b; c".to_tendril();
+        let parts: Vec<_> = t.split_on_any(&ac).iter().map(|s| s.to_string()).collect();
+        assert_eq!(vec!["a", "b", "c"], parts);
+    }
+
+    #[test]
+    fn replace_all() {
+        let ac = Automaton::new(&["foo", "bar"]);
+        let t = "foo and bar".to_tendril();
+        let out = t.replace_all(&ac, &"X".to_tendril());
+        assert_eq!("X and X", &*out);
+    }
+
+    #[test]
+    fn matches_shared() {
+        let ac = Automaton::new(&["beta"]);
+        let t = "alpha beta gamma".to_tendril();
+        let ms = t.matches(&ac);
+        assert_eq!(1, ms.len());
+        assert_eq!("beta", &*ms[0].1);
+    }
+
+    #[test]
+    fn single_pattern_prefilter() {
+        let pat = SinglePattern::new("xyz");
+        let t = "a xyz b xyz c".to_tendril();
+        let hits = t.find_pattern(&pat);
+        assert_eq!(2, hits.len());
+        for h in &hits {
+            assert_eq!("xyz", &**h);
+        }
+
+        // No spurious match when the rare byte appears without the pattern.
+        let pat = SinglePattern::new("zoo");
+        assert_eq!(0, "z o o".to_tendril().find_pattern(&pat).len());
+    }
+}
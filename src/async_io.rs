@@ -0,0 +1,91 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Async I/O support for byte tendrils, behind the `tokio` feature.
+//!
+//! Mirrors the synchronous `ReadExt::read_to_tendril` and the
+//! `io::Write` impl, filling a `ByteTendril` directly from an async
+//! source with the same exponential-growth + `push_uninitialized`/
+//! `pop_back` strategy, so no intermediate `Vec` is copied.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+
+use fmt;
+use tendril::Tendril;
+
+const DEFAULT_BUF_SIZE: u32 = 64 * 1024;
+
+/// Async counterpart to `ReadExt`.
+pub trait AsyncReadExt2: AsyncRead + Unpin {
+    /// Read all bytes until EOF into `buf`, returning the number read.
+    fn read_to_tendril<'a>(&'a mut self, buf: &'a mut Tendril<fmt::Bytes>)
+        -> Pin<Box<dyn std::future::Future<Output = io::Result<usize>> + 'a>>
+        where Self: 'a;
+}
+
+impl<T> AsyncReadExt2 for T
+    where T: AsyncRead + Unpin,
+{
+    fn read_to_tendril<'a>(&'a mut self, buf: &'a mut Tendril<fmt::Bytes>)
+        -> Pin<Box<dyn std::future::Future<Output = io::Result<usize>> + 'a>>
+        where Self: 'a,
+    {
+        Box::pin(async move {
+            let start_len = buf.len();
+            let mut len = start_len;
+            let mut new_write_size = 16;
+            loop {
+                if len == buf.len() {
+                    if new_write_size < DEFAULT_BUF_SIZE {
+                        new_write_size *= 2;
+                    }
+                    unsafe {
+                        buf.push_uninitialized(new_write_size);
+                    }
+                }
+
+                match self.read(&mut buf[len..]).await {
+                    Ok(0) => break,
+                    Ok(n) => len += n,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => {
+                        let buf_len = buf.len32();
+                        buf.pop_back(buf_len - (len as u32));
+                        return Err(e);
+                    }
+                }
+            }
+
+            let buf_len = buf.len32();
+            buf.pop_back(buf_len - (len as u32));
+            Ok(len - start_len)
+        })
+    }
+}
+
+impl AsyncWrite for Tendril<fmt::Bytes> {
+    #[inline]
+    fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8])
+        -> Poll<io::Result<usize>>
+    {
+        self.get_mut().push_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -5,26 +5,91 @@
 // except according to those terms.
 
 //! Provides an unsafe owned buffer type, used in implementing `Tendril`.
-
-use std::{mem, ptr, cmp, u32, slice};
-use std::rt::heap;
+//!
+//! `Buf32<H, A>` is generic over the `Allocator` trait below, but
+//! `Tendril<F>` itself only ever instantiates it as `Buf32<Header, Global>`
+//! (see the four `Buf32<Header>` call sites in `tendril.rs`) and has no type
+//! parameter of its own to pick anything else. So `A` is internal
+//! extensibility, not a consumer-facing feature yet: there is currently no
+//! way for a caller to get a `Tendril` backed by a custom allocator. Making
+//! that reachable means adding an `A` parameter to `Tendril<F>` itself and
+//! threading it through every impl in this crate that mentions `Tendril`,
+//! which is a real API change, not a follow-on to this trait existing.
+
+use core::{mem, ptr, cmp, slice};
+use core::marker::PhantomData;
+
+use alloc::alloc::{self, Layout};
 
 use OFLOW;
 
 pub const MIN_CAP: u32 = 16;
 
-// NB: This alignment must be sufficient for H!
+// Floor alignment for allocations with a zero-sized or byte-aligned `H`.
 pub const MIN_ALIGN: usize = 4;
 
 pub const MAX_LEN: usize = u32::MAX as usize;
 
+/// Alignment to request from the allocator: at least `MIN_ALIGN`, and
+/// always enough for `H` itself (e.g. an `AtomicUsize`-bearing header
+/// needs its native alignment, not just the historical floor).
+#[inline(always)]
+fn align_for<H>() -> usize {
+    cmp::max(MIN_ALIGN, mem::align_of::<H>())
+}
+
+/// Abstracts the heap backing a `Buf32`.
+///
+/// The methods mirror the raw allocator API. `usable_size` may return a
+/// capacity larger than requested when the allocator rounds up. The
+/// trait is `unsafe` because implementors must return correctly aligned,
+/// live allocations.
+pub unsafe trait Allocator {
+    unsafe fn allocate(size: usize, align: usize) -> *mut u8;
+    unsafe fn reallocate(ptr: *mut u8, old_size: usize, new_size: usize,
+                         align: usize) -> *mut u8;
+    unsafe fn deallocate(ptr: *mut u8, size: usize, align: usize);
+
+    #[inline(always)]
+    fn usable_size(size: usize, _align: usize) -> usize {
+        size
+    }
+}
+
+/// The default allocator, backed by the global heap.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Global;
+
+unsafe impl Allocator for Global {
+    #[inline(always)]
+    unsafe fn allocate(size: usize, align: usize) -> *mut u8 {
+        alloc::alloc(Layout::from_size_align_unchecked(size, align))
+    }
+
+    #[inline(always)]
+    unsafe fn reallocate(ptr: *mut u8, old_size: usize, new_size: usize,
+                         align: usize) -> *mut u8 {
+        alloc::realloc(ptr, Layout::from_size_align_unchecked(old_size, align), new_size)
+    }
+
+    #[inline(always)]
+    unsafe fn deallocate(ptr: *mut u8, size: usize, align: usize) {
+        alloc::dealloc(ptr, Layout::from_size_align_unchecked(size, align))
+    }
+
+    // `GlobalAlloc` has no stable "how much did I actually get" query, so
+    // this falls back to the trait's default (no over-allocation tracking)
+    // rather than the old `heap::usable_size` rounding.
+}
+
 /// A buffer points to a header of type `H`, which is followed by `MIN_CAP` or more
-/// bytes of storage.
+/// bytes of storage. `A` selects the heap implementation.
 #[repr(packed)]
-pub struct Buf32<H> {
+pub struct Buf32<H, A = Global> {
     pub ptr: *mut H,
     pub len: u32,
     pub cap: u32,
+    pub marker: PhantomData<A>,
 }
 
 #[inline(always)]
@@ -34,24 +99,25 @@ fn add_header<H>(x: u32) -> usize {
 }
 
 #[inline(always)]
-fn full_cap<H>(size: usize) -> u32 {
+fn full_cap<H, A: Allocator>(size: usize) -> u32 {
     cmp::min(u32::MAX as usize,
-        heap::usable_size(size, MIN_ALIGN)
+        A::usable_size(size, align_for::<H>())
             .checked_sub(mem::size_of::<H>())
             .expect(OFLOW)) as u32
 }
 
-impl<H> Buf32<H> {
+impl<H, A> Buf32<H, A> where A: Allocator {
     #[inline]
-    pub unsafe fn with_capacity(mut cap: u32, h: H) -> Buf32<H> {
+    pub unsafe fn with_capacity(mut cap: u32, h: H) -> Buf32<H, A> {
         if cap < MIN_CAP {
             cap = MIN_CAP;
         }
 
         let alloc_size = add_header::<H>(cap);
-        let ptr = heap::allocate(alloc_size, MIN_ALIGN);
+        let align = align_for::<H>();
+        let ptr = A::allocate(alloc_size, align);
         if ptr.is_null() {
-            ::alloc::oom();
+            alloc::handle_alloc_error(Layout::from_size_align_unchecked(alloc_size, align));
         }
 
         let ptr = ptr as *mut H;
@@ -60,14 +126,15 @@ impl<H> Buf32<H> {
         Buf32 {
             ptr: ptr,
             len: 0,
-            cap: full_cap::<H>(alloc_size),
+            cap: full_cap::<H, A>(alloc_size),
+            marker: PhantomData,
         }
     }
 
     #[inline]
     pub unsafe fn destroy(self) {
         let alloc_size = add_header::<H>(self.cap);
-        heap::deallocate(self.ptr as *mut u8, alloc_size, MIN_ALIGN);
+        A::deallocate(self.ptr as *mut u8, alloc_size, align_for::<H>());
     }
 
     #[inline(always)]
@@ -96,16 +163,17 @@ impl<H> Buf32<H> {
 
         let new_cap = new_cap.checked_next_power_of_two().expect(OFLOW);
         let alloc_size = add_header::<H>(new_cap);
-        let ptr = heap::reallocate(self.ptr as *mut u8,
-                                   add_header::<H>(new_cap),
-                                   alloc_size,
-                                   MIN_ALIGN);
+        let align = align_for::<H>();
+        let ptr = A::reallocate(self.ptr as *mut u8,
+                                add_header::<H>(self.cap),
+                                alloc_size,
+                                align);
         if ptr.is_null() {
-            ::alloc::oom();
+            alloc::handle_alloc_error(Layout::from_size_align_unchecked(alloc_size, align));
         }
 
         self.ptr = ptr as *mut H;
-        self.cap = full_cap::<H>(alloc_size);
+        self.cap = full_cap::<H, A>(alloc_size);
     }
 }
 
@@ -117,7 +185,7 @@ mod test {
     #[test]
     fn smoke_test() {
         unsafe {
-            let mut b = Buf32::with_capacity(0, ());
+            let mut b = Buf32::<(), _>::with_capacity(0, ());
             assert_eq!(b"", b.data());
 
             b.grow(5);
@@ -0,0 +1,162 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A stateful, pull-style UTF-8 decoder for streamed byte chunks.
+//!
+//! Unlike the push-based `stream::UTF8Validator`, `Utf8Decoder` is
+//! driven by the caller: feed it arbitrary `ByteTendril` chunks as they
+//! arrive from I/O and pull out `StrTendril` pieces. Any trailing
+//! partial multi-byte sequence (at most three bytes) is held over until
+//! the next chunk completes it.
+
+use std::str;
+
+use tendril::{ByteTendril, StrTendril, Tendril};
+
+/// Error returned when a chunk contains a genuinely invalid sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Utf8Error {
+    /// The valid text decoded before the bad bytes.
+    pub valid_prefix: StrTendril,
+}
+
+/// Incremental UTF-8 decoder over streamed `ByteTendril` chunks.
+pub struct Utf8Decoder {
+    /// Held-over bytes of an incomplete trailing sequence (<= 3 bytes).
+    carry: ByteTendril,
+    /// Emit U+FFFD on invalid input instead of erroring.
+    lossy: bool,
+}
+
+impl Utf8Decoder {
+    /// Create a decoder that errors on invalid input.
+    #[inline]
+    pub fn new() -> Utf8Decoder {
+        Utf8Decoder {
+            carry: Tendril::new(),
+            lossy: false,
+        }
+    }
+
+    /// Create a decoder that substitutes U+FFFD for invalid input.
+    #[inline]
+    pub fn new_lossy() -> Utf8Decoder {
+        Utf8Decoder {
+            carry: Tendril::new(),
+            lossy: true,
+        }
+    }
+
+    /// Feed one chunk, returning the text decodable so far.
+    ///
+    /// When no held-over bytes had to be prepended and no replacement
+    /// was made, the returned tendril is a subtendril of `chunk`: it
+    /// shares `chunk`'s buffer unless it is short enough to be stored
+    /// inline.
+    pub fn feed(&mut self, chunk: ByteTendril) -> Result<StrTendril, Utf8Error> {
+        let combined = if self.carry.len32() == 0 {
+            chunk
+        } else {
+            let mut c = self.carry.clone();
+            c.push_tendril(&chunk);
+            self.carry.clear();
+            c
+        };
+
+        self.decode(combined)
+    }
+
+    fn decode(&mut self, combined: ByteTendril) -> Result<StrTendril, Utf8Error> {
+        let (valid_up_to, incomplete) = match str::from_utf8(&combined) {
+            Ok(_) => (combined.len32(), false),
+            Err(e) => (e.valid_up_to() as u32, e.error_len().is_none()),
+        };
+
+        let valid = combined.subtendril(0, valid_up_to);
+        let valid: StrTendril = unsafe { valid.reinterpret_without_validating() };
+        let tail_len = combined.len32() - valid_up_to;
+
+        if tail_len == 0 {
+            return Ok(valid);
+        }
+
+        if incomplete {
+            // At most three bytes of an in-progress sequence.
+            self.carry = combined.subtendril(valid_up_to, tail_len);
+            return Ok(valid);
+        }
+
+        // Genuinely invalid bytes at `valid_up_to`.
+        if self.lossy {
+            let mut out = valid;
+            out.push_char('\u{fffd}');
+            // Skip the single offending byte and resume.
+            let rest = combined.subtendril(valid_up_to + 1, tail_len - 1);
+            let more = self.decode(rest)?;
+            out.push_tendril(&more);
+            Ok(out)
+        } else {
+            Err(Utf8Error { valid_prefix: valid })
+        }
+    }
+
+    /// Finish the stream, reporting any dangling incomplete sequence.
+    ///
+    /// In lossy mode the dangling bytes become a single U+FFFD.
+    pub fn finish(mut self) -> Result<StrTendril, Utf8Error> {
+        if self.carry.len32() == 0 {
+            return Ok(Tendril::new());
+        }
+        if self.lossy {
+            let mut out: StrTendril = Tendril::new();
+            out.push_char('\u{fffd}');
+            Ok(out)
+        } else {
+            Err(Utf8Error { valid_prefix: Tendril::new() })
+        }
+    }
+}
+
+impl Default for Utf8Decoder {
+    #[inline]
+    fn default() -> Utf8Decoder {
+        Utf8Decoder::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Utf8Decoder;
+    use tendril::SliceExt;
+
+    #[test]
+    fn split_across_chunks() {
+        let mut d = Utf8Decoder::new();
+        // U+A66E is EA 99 AE; split after the first byte.
+        assert_eq!("xy", &*d.feed(b"xy\xEA".to_tendril()).unwrap());
+        assert_eq!("\u{a66e}z", &*d.feed(b"\x99\xAEz".to_tendril()).unwrap());
+        assert_eq!("", &*d.finish().unwrap());
+    }
+
+    #[test]
+    fn invalid_errors() {
+        let mut d = Utf8Decoder::new();
+        assert!(d.feed(b"ab\xFFcd".to_tendril()).is_err());
+    }
+
+    #[test]
+    fn invalid_lossy() {
+        let mut d = Utf8Decoder::new_lossy();
+        assert_eq!("ab\u{fffd}cd", &*d.feed(b"ab\xFFcd".to_tendril()).unwrap());
+    }
+
+    #[test]
+    fn incomplete_at_finish() {
+        let mut d = Utf8Decoder::new();
+        assert_eq!("", &*d.feed(b"\xEA\x99".to_tendril()).unwrap());
+        assert!(d.finish().is_err());
+    }
+}
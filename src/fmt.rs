@@ -254,6 +254,70 @@ unsafe impl Slice for str {
     }
 }
 
+/// Read a `u16` code unit from `buf` at byte offset `i`.
+#[inline]
+fn utf16_unit(buf: &[u8], i: usize, big_endian: bool) -> u16 {
+    let (hi, lo) = if big_endian {
+        (buf[i], buf[i + 1])
+    } else {
+        (buf[i + 1], buf[i])
+    };
+    ((hi as u16) << 8) | (lo as u16)
+}
+
+/// Validate a buffer as UTF-16 with the given endianness.
+///
+/// The length must be even and every surrogate must be paired.
+#[inline]
+fn validate_utf16(buf: &[u8], big_endian: bool) -> bool {
+    if buf.len() % 2 != 0 {
+        return false;
+    }
+    let mut i = 0;
+    while i < buf.len() {
+        let u = utf16_unit(buf, i, big_endian);
+        i += 2;
+        match u {
+            0xD800..=0xDBFF => {
+                // High surrogate: must be followed by a low surrogate.
+                if i >= buf.len() {
+                    return false;
+                }
+                let lo = utf16_unit(buf, i, big_endian);
+                if !(0xDC00..=0xDFFF).contains(&lo) {
+                    return false;
+                }
+                i += 2;
+            }
+            0xDC00..=0xDFFF => return false, // unpaired trail surrogate
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Marker type for little-endian UTF-16 text.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct UTF16LE;
+
+unsafe impl Format for UTF16LE {
+    #[inline]
+    fn validate(buf: &[u8]) -> bool {
+        validate_utf16(buf, false)
+    }
+}
+
+/// Marker type for big-endian UTF-16 text.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct UTF16BE;
+
+unsafe impl Format for UTF16BE {
+    #[inline]
+    fn validate(buf: &[u8]) -> bool {
+        validate_utf16(buf, true)
+    }
+}
+
 /// Marker type for WTF-8 text.
 ///
 /// See the [WTF-8 spec](http://simonsapin.github.io/wtf-8/).
@@ -0,0 +1,208 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Incremental decoding of legacy encodings across chunk boundaries.
+//!
+//! `IncrementalDecoder` wraps a rust-encoding `EncodingRef` and lets a
+//! multibyte sequence straddle a `feed` boundary: the trailing bytes of
+//! an incomplete code unit are held in a small internal buffer and
+//! prepended to the next `feed`. `finish` reports `UnexpectedEof` when
+//! leftover bytes remain that cannot form a valid sequence, mirroring
+//! `Read::read_exact`'s EOF contract.
+
+use std::io;
+
+use encoding::{EncodingRef, RawDecoder, RawEncoder, DecoderTrap, EncoderTrap};
+
+use tendril::{ByteTendril, StrTendril, Tendril};
+
+/// Name matching the chunked decoder in request terms; the existing
+/// `IncrementalDecoder` already feeds `ByteTendril` chunks and emits
+/// `StrTendril`, so the two are the same type.
+pub type TendrilDecoder = IncrementalDecoder;
+
+/// A stateful decoder producing `StrTendril` output chunk by chunk.
+pub struct IncrementalDecoder {
+    decoder: Box<RawDecoder>,
+    trap: DecoderTrap,
+    /// Unconsumed trailing bytes of an incomplete sequence.
+    held: ByteTendril,
+}
+
+impl IncrementalDecoder {
+    /// Create a decoder for `encoding`, applying `trap` to malformed bytes.
+    #[inline]
+    pub fn new(encoding: EncodingRef, trap: DecoderTrap) -> IncrementalDecoder {
+        IncrementalDecoder {
+            decoder: encoding.raw_decoder(),
+            trap: trap,
+            held: Tendril::new(),
+        }
+    }
+
+    /// Feed one chunk and return everything decodable so far.
+    pub fn feed(&mut self, chunk: &ByteTendril) -> StrTendril {
+        let mut input = if self.held.len32() == 0 {
+            chunk.clone()
+        } else {
+            let mut i = self.held.clone();
+            i.push_tendril(chunk);
+            self.held.clear();
+            i
+        };
+
+        let mut out: StrTendril = Tendril::new();
+        loop {
+            let (nread, err) = self.decoder.raw_feed(&input, &mut out);
+            match err {
+                Some(e) => {
+                    // A genuine error mid-stream: apply the trap and resume.
+                    debug_assert!(e.upto >= 0);
+                    let bad = input.subtendril(nread as u32, (e.upto - nread as isize) as u32);
+                    if !self.trap.trap(&mut *self.decoder, &bad, &mut out) {
+                        // Strict trap writes nothing: substitute U+FFFD and
+                        // carry on. `feed` does not report the error, so
+                        // Strict produces the same output as Replace here.
+                        out.push_char('\u{fffd}');
+                    }
+                    input.pop_front(e.upto as u32);
+                }
+                None => {
+                    // `nread` bytes were consumed; anything after is an
+                    // incomplete sequence to retain for the next feed.
+                    let rest = input.len32() - nread as u32;
+                    if rest > 0 {
+                        self.held = input.subtendril(nread as u32, rest);
+                    }
+                    break;
+                }
+            }
+        }
+        out
+    }
+
+    /// Flush the decoder, erroring if an incomplete sequence remains.
+    pub fn finish(mut self) -> Result<StrTendril, io::Error> {
+        let mut out: StrTendril = Tendril::new();
+        if self.held.len32() != 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                "incomplete byte sequence at end of stream"));
+        }
+        if let Some(_e) = self.decoder.raw_finish(&mut out) {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                "incomplete byte sequence at end of stream"));
+        }
+        Ok(out)
+    }
+}
+
+/// A stateful encoder producing `ByteTendril` output chunk by chunk.
+///
+/// The counterpart to [`IncrementalDecoder`]: a character split across a
+/// `feed` boundary (which can only happen when a caller slices a
+/// `StrTendril` on a non-char edge) is retained and prepended to the next
+/// chunk, so no scalar value is ever lost.
+pub struct TendrilEncoder {
+    encoder: Box<RawEncoder>,
+    trap: EncoderTrap,
+    /// A trailing byte run that did not form a complete scalar value.
+    held: StrTendril,
+}
+
+impl TendrilEncoder {
+    /// Create an encoder for `encoding`, applying `trap` to unencodable chars.
+    #[inline]
+    pub fn new(encoding: EncodingRef, trap: EncoderTrap) -> TendrilEncoder {
+        TendrilEncoder {
+            encoder: encoding.raw_encoder(),
+            trap: trap,
+            held: Tendril::new(),
+        }
+    }
+
+    /// Feed one chunk and return everything encodable so far.
+    pub fn feed(&mut self, chunk: &StrTendril) -> ByteTendril {
+        let mut input = if self.held.len32() == 0 {
+            chunk.clone()
+        } else {
+            let mut i = self.held.clone();
+            i.push_tendril(chunk);
+            self.held.clear();
+            i
+        };
+
+        let mut out: ByteTendril = Tendril::new();
+        loop {
+            let (nread, err) = self.encoder.raw_feed(&input, &mut out);
+            match err {
+                Some(e) => {
+                    debug_assert!(e.upto >= 0);
+                    let bad = input.subtendril(nread as u32, (e.upto - nread as isize) as u32);
+                    let _ = self.trap.trap(&mut *self.encoder, &bad, &mut out);
+                    input.pop_front(e.upto as u32);
+                }
+                None => {
+                    let rest = input.len32() - nread as u32;
+                    if rest > 0 {
+                        self.held = input.subtendril(nread as u32, rest);
+                    }
+                    break;
+                }
+            }
+        }
+        out
+    }
+
+    /// Flush the encoder, erroring if an incomplete character remains.
+    pub fn finish(mut self) -> Result<ByteTendril, io::Error> {
+        let mut out: ByteTendril = Tendril::new();
+        if self.held.len32() != 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                "incomplete character at end of stream"));
+        }
+        if let Some(_e) = self.encoder.raw_finish(&mut out) {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                "incomplete character at end of stream"));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IncrementalDecoder, TendrilEncoder};
+    use tendril::SliceExt;
+    use encoding::all as enc;
+    use encoding::{DecoderTrap, EncoderTrap};
+
+    #[test]
+    fn split_multibyte() {
+        let mut d = IncrementalDecoder::new(enc::UTF_8, DecoderTrap::Strict);
+        let mut out = String::new();
+        out.push_str(&d.feed(&b"\xEA\x99".to_tendril()));
+        out.push_str(&d.feed(&b"\xAEz".to_tendril()));
+        out.push_str(&d.finish().unwrap());
+        assert_eq!("\u{a66e}z", out);
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let mut d = IncrementalDecoder::new(enc::UTF_8, DecoderTrap::Strict);
+        let _ = d.feed(&b"\xEA\x99".to_tendril());
+        assert!(d.finish().is_err());
+    }
+
+    #[test]
+    fn encode_chunks() {
+        let mut e = TendrilEncoder::new(enc::KOI8_U, EncoderTrap::Strict);
+        let mut out = Vec::new();
+        out.extend_from_slice(&e.feed(&"Эне".to_tendril()));
+        out.extend_from_slice(&e.feed(&"ргия".to_tendril()));
+        out.extend_from_slice(&e.finish().unwrap());
+        let back = out.to_tendril().decode(enc::KOI8_U, DecoderTrap::Strict).unwrap();
+        assert_eq!("Энергия", &*back);
+    }
+}
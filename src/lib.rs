@@ -20,8 +20,16 @@
     clippy::unseparated_literal_suffix
 )]
 #![cfg_attr(all(test, feature = "bench"), feature(test))]
+#![cfg_attr(not(feature = "std"), no_std)]
 //#![cfg_attr(test, deny(warnings))]
 
+// `alloc` is always required — it backs `Buf32` and the owned/shared
+// representations. `std` is an additive feature that enables the
+// `io::Write`/`ReadExt` surface and the `encoding`-based helpers.
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 #[cfg(feature = "encoding")]
 pub extern crate encoding;
 #[cfg(feature = "encoding_rs")]
@@ -33,12 +41,31 @@ extern crate mac;
 
 pub use crate::fmt::Format;
 pub use crate::stream::TendrilSink;
-pub use crate::tendril::{Atomic, Atomicity, NonAtomic, SendTendril};
-pub use crate::tendril::{ByteTendril, ReadExt, SliceExt, StrTendril, SubtendrilError, Tendril};
-pub use crate::utf8_decode::IncompleteUtf8;
+pub use crate::tendril::SendTendril;
+pub use crate::tendril::{SendByteTendril, SendStrTendril};
+pub use crate::tendril::{ByteTendril, SliceExt, StrTendril, SubtendrilError, Tendril};
+#[cfg(feature = "std")]
+pub use crate::tendril::ReadExt;
+pub use crate::utf8_decode::{IncompleteUtf8, IncrementalUtf8Decoder};
 
+pub mod ac;
+#[cfg(feature = "tokio")]
+pub mod async_io;
+pub mod decode;
 pub mod fmt;
+#[cfg(feature = "encoding")]
+pub mod incremental;
+#[cfg(feature = "std")]
+pub mod osstr;
+pub mod split;
 pub mod stream;
+pub mod structured;
+#[cfg(feature = "encoding")]
+pub mod transcode;
+pub mod utf16;
+
+#[cfg(feature = "serde")]
+mod serde_impl;
 
 mod buf32;
 mod tendril;
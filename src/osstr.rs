@@ -0,0 +1,185 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Bridge between `Tendril<fmt::WTF8>` and `OsStr`/`OsString`.
+//!
+//! WTF-8 exists precisely to hold platform-native string data without a
+//! lossy UTF-8 conversion. On Windows an `OsStr` is already WTF-8-shaped
+//! (potentially ill-formed UTF-16), so the conversions are cheap; on
+//! Unix an `OsStr` is arbitrary bytes, so we bridge through
+//! `Tendril<fmt::Bytes>` with a validating path.
+
+use std::ffi::{OsStr, OsString};
+
+use fmt;
+use tendril::{ByteTendril, Tendril};
+
+type Wtf8Tendril = Tendril<fmt::WTF8>;
+
+impl Wtf8Tendril {
+    /// Wrap raw bytes as WTF-8, validating the encoding.
+    ///
+    /// Returns `Err(())` if `bytes` is not well-formed WTF-8.
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Wtf8Tendril, ()> {
+        ByteTendril::from_slice(bytes)
+            .try_reinterpret::<fmt::WTF8>()
+            .map_err(|_| ())
+    }
+}
+
+#[cfg(unix)]
+impl Wtf8Tendril {
+    /// Build a WTF-8 tendril from an `OsStr`.
+    ///
+    /// On Unix an `OsStr` is arbitrary bytes. Input that is valid WTF-8
+    /// is kept as is; otherwise the whole input goes through
+    /// `String::from_utf8_lossy`, which replaces each invalid UTF-8
+    /// sequence, including any WTF-8 surrogate encoding, with `U+FFFD`.
+    pub fn from_os_str(s: &OsStr) -> Wtf8Tendril {
+        use std::os::unix::ffi::OsStrExt;
+        let bytes = s.as_bytes();
+        match Wtf8Tendril::try_from_bytes(bytes) {
+            Ok(t) => t,
+            Err(()) => {
+                let lossy = String::from_utf8_lossy(bytes);
+                Tendril::from_slice(&lossy).try_reinterpret::<fmt::WTF8>()
+                    .unwrap_or_else(|_| Tendril::new())
+            }
+        }
+    }
+
+    /// Convert a WTF-8 tendril into an `OsString`.
+    pub fn into_os_string(self) -> OsString {
+        use std::os::unix::ffi::OsStringExt;
+        let bytes = unsafe { self.reinterpret_without_validating::<fmt::Bytes>() };
+        OsString::from_vec((&*bytes).to_vec())
+    }
+}
+
+#[cfg(windows)]
+impl Wtf8Tendril {
+    /// Build a WTF-8 tendril from an `OsStr`.
+    ///
+    /// On Windows an `OsStr` is (possibly ill-formed) UTF-16, which maps
+    /// directly onto WTF-8.
+    pub fn from_os_str(s: &OsStr) -> Wtf8Tendril {
+        use std::os::windows::ffi::OsStrExt;
+        let units: Vec<u16> = s.encode_wide().collect();
+        let mut out: ByteTendril = Tendril::new();
+        let mut i = 0;
+        while i < units.len() {
+            let u = units[i];
+            i += 1;
+            let cp = match u {
+                0xD800..=0xDBFF if i < units.len()
+                    && (0xDC00..=0xDFFF).contains(&units[i]) => {
+                    let lo = units[i];
+                    i += 1;
+                    0x10000 + (((u - 0xD800) as u32) << 10) + ((lo - 0xDC00) as u32)
+                }
+                _ => u as u32,
+            };
+            push_wtf8(&mut out, cp);
+        }
+        unsafe { out.reinterpret_without_validating::<fmt::WTF8>() }
+    }
+
+    /// Convert a WTF-8 tendril into an `OsString`.
+    pub fn into_os_string(self) -> OsString {
+        use std::os::windows::ffi::OsStringExt;
+        let buf = unsafe { self.reinterpret_without_validating::<fmt::Bytes>() };
+        let bytes: &[u8] = &buf;
+        let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let (cp, adv) = decode_wtf8(&bytes[i..]);
+            i += adv;
+            if cp <= 0xFFFF {
+                units.push(cp as u16);
+            } else {
+                let c = cp - 0x10000;
+                units.push(0xD800 + (c >> 10) as u16);
+                units.push(0xDC00 + (c & 0x3FF) as u16);
+            }
+        }
+        OsString::from_wide(&units)
+    }
+}
+
+/// Decode one WTF-8 scalar (or unpaired surrogate) from the front of
+/// `bytes`, returning the code point and the number of bytes consumed.
+#[cfg(windows)]
+fn decode_wtf8(bytes: &[u8]) -> (u32, usize) {
+    let b0 = bytes[0];
+    if b0 < 0x80 {
+        (b0 as u32, 1)
+    } else if b0 < 0xE0 {
+        (((b0 as u32 & 0x1F) << 6) | (bytes[1] as u32 & 0x3F), 2)
+    } else if b0 < 0xF0 {
+        (((b0 as u32 & 0x0F) << 12)
+            | ((bytes[1] as u32 & 0x3F) << 6)
+            | (bytes[2] as u32 & 0x3F), 3)
+    } else {
+        (((b0 as u32 & 0x07) << 18)
+            | ((bytes[1] as u32 & 0x3F) << 12)
+            | ((bytes[2] as u32 & 0x3F) << 6)
+            | (bytes[3] as u32 & 0x3F), 4)
+    }
+}
+
+/// Encode a code point (possibly an unpaired surrogate) as WTF-8.
+#[cfg(windows)]
+fn push_wtf8(out: &mut ByteTendril, cp: u32) {
+    if cp < 0x80 {
+        out.push_slice(&[cp as u8]);
+    } else if cp < 0x800 {
+        out.push_slice(&[0xC0 | (cp >> 6) as u8, 0x80 | (cp & 0x3F) as u8]);
+    } else if cp < 0x10000 {
+        out.push_slice(&[
+            0xE0 | (cp >> 12) as u8,
+            0x80 | ((cp >> 6) & 0x3F) as u8,
+            0x80 | (cp & 0x3F) as u8,
+        ]);
+    } else {
+        out.push_slice(&[
+            0xF0 | (cp >> 18) as u8,
+            0x80 | ((cp >> 12) & 0x3F) as u8,
+            0x80 | ((cp >> 6) & 0x3F) as u8,
+            0x80 | (cp & 0x3F) as u8,
+        ]);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::ffi::OsStr;
+
+    use super::Wtf8Tendril;
+    use fmt;
+    use tendril::Tendril;
+
+    #[test]
+    fn round_trip_ascii() {
+        let t = Wtf8Tendril::from_os_str(OsStr::new("hello"));
+        let os = t.into_os_string();
+        assert_eq!(OsStr::new("hello"), &*os);
+    }
+
+    #[test]
+    fn try_from_bytes_validates() {
+        // A well-formed UTF-8 byte string is accepted.
+        assert!(Wtf8Tendril::try_from_bytes(b"ok").is_ok());
+        // A lone high surrogate (WTF-8) is accepted.
+        assert!(Wtf8Tendril::try_from_bytes(b"\xED\xA0\x80").is_ok());
+        // Raw invalid UTF-8 is rejected.
+        assert!(Wtf8Tendril::try_from_bytes(b"\xFF").is_err());
+    }
+
+    #[test]
+    fn from_bytes_type() {
+        let _: Tendril<fmt::WTF8> = Wtf8Tendril::try_from_bytes(b"x").unwrap();
+    }
+}
@@ -4,37 +4,207 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+//! Optional `serde` support, behind the `serde` feature.
+//!
+//! `StrTendril` serializes as a string. `ByteTendril` serializes as a
+//! lowercase hex string for human-readable serializers (e.g. JSON) and
+//! as a byte sequence otherwise. Deserialization builds straight into inline or owned
+//! storage via `try_from_byte_slice`/`push_slice`, validating with the
+//! format machinery and surfacing a `de::Error` on malformed input
+//! rather than panicking or round-tripping through `String`.
+//!
+//! The impls are generic over the format marker through the private
+//! `SerdeFormat` trait, dispatched from a single pair of blanket impls.
+//! Only `fmt::UTF8` and `fmt::Bytes` implement it; other formats
+//! (`ASCII`, `Latin1`, `WTF8`, ...) have no serde support. `Tendril<F>`
+//! takes only the one type
+//! parameter, so there is nothing else to generalize over here.
+
 use serde::{
-    de::{Error, Visitor},
-    Deserialize, Serialize, Serializer,
+    de::{Error, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 
-use crate::StrTendril;
-use std::fmt;
+use crate::{fmt, ByteTendril, SendTendril, StrTendril, Tendril};
+use std::fmt as std_fmt;
+
+/// Format-specific serialization behavior, dispatched from the blanket
+/// `Serialize`/`Deserialize` impls on `Tendril<F>`.
+trait SerdeFormat: fmt::SliceFormat {
+    fn serialize<S: Serializer>(t: &Tendril<Self>, s: S) -> Result<S::Ok, S::Error>;
+    fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Tendril<Self>, D::Error>;
+}
+
+impl SerdeFormat for fmt::UTF8 {
+    fn serialize<S: Serializer>(t: &Tendril<Self>, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&t[..])
+    }
 
-impl Serialize for StrTendril {
+    fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Tendril<Self>, D::Error> {
+        d.deserialize_str(StrVisitor)
+    }
+}
+
+impl SerdeFormat for fmt::Bytes {
+    fn serialize<S: Serializer>(t: &Tendril<Self>, s: S) -> Result<S::Ok, S::Error> {
+        // Human-readable formats (e.g. JSON) get a hex string so the
+        // payload is legible; binary formats get a length-prefixed byte
+        // string that stays byte-exact.
+        if s.is_human_readable() {
+            s.serialize_str(&to_hex(&t[..]))
+        } else {
+            s.serialize_bytes(&t[..])
+        }
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Tendril<Self>, D::Error> {
+        if d.is_human_readable() {
+            d.deserialize_str(HexVisitor)
+        } else {
+            d.deserialize_bytes(BytesVisitor)
+        }
+    }
+}
+
+/// Encode bytes as a lowercase hex string.
+fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        out.push(DIGITS[(b >> 4) as usize] as char);
+        out.push(DIGITS[(b & 0x0F) as usize] as char);
+    }
+    out
+}
+
+/// Decode a single hex digit, or `None` if it is not one.
+fn from_hex_digit(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+impl<F> Serialize for Tendril<F>
+    where F: SerdeFormat,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        <F as SerdeFormat>::serialize(self, serializer)
+    }
+}
+
+impl<'de, F> Deserialize<'de> for Tendril<F>
+    where F: SerdeFormat,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <F as SerdeFormat>::deserialize(deserializer)
+    }
+}
+
+impl<F> Serialize for SendTendril<F>
+    where F: SerdeFormat,
+{
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        self[..].serialize(serializer)
+        self.as_tendril().serialize(serializer)
+    }
+}
+
+impl<'de, F> Deserialize<'de> for SendTendril<F>
+    where F: SerdeFormat,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Tendril::<F>::deserialize(deserializer).map(Tendril::into_send)
     }
 }
 
-struct TendrilVisitor;
+struct StrVisitor;
 
-impl<'de> Visitor<'de> for TendrilVisitor {
+impl<'de> Visitor<'de> for StrVisitor {
     type Value = StrTendril;
 
+    fn expecting(&self, f: &mut std_fmt::Formatter) -> std_fmt::Result {
+        write!(f, "a UTF-8 string")
+    }
+
     fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
         Ok(StrTendril::from_slice(v))
     }
 
-    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "a tendril string")
+    fn visit_borrowed_str<E: Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        self.visit_str(v)
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        StrTendril::try_from_byte_slice(v)
+            .map_err(|()| E::custom("invalid UTF-8 for StrTendril"))
+    }
+
+    fn visit_borrowed_bytes<E: Error>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+        self.visit_bytes(v)
     }
 }
 
-impl<'de> Deserialize<'de> for StrTendril {
-    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        deserializer.deserialize_str(TendrilVisitor)
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = ByteTendril;
+
+    fn expecting(&self, f: &mut std_fmt::Formatter) -> std_fmt::Result {
+        write!(f, "a byte sequence")
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(ByteTendril::from_slice(v))
+    }
+
+    fn visit_borrowed_bytes<E: Error>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+        self.visit_bytes(v)
+    }
+
+    fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        // One bulk copy, rather than `v.into_iter().collect()`, which
+        // would go through `Extend<u8>` and `push_slice` one byte at a
+        // time.
+        Ok(ByteTendril::from_slice(&v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut t: ByteTendril = Tendril::new();
+        if let Some(hint) = seq.size_hint() {
+            t.reserve(hint as u32);
+        }
+        while let Some(b) = seq.next_element::<u8>()? {
+            t.push_slice(&[b]);
+        }
+        Ok(t)
+    }
+}
+
+/// Visitor for the human-readable (hex string) byte representation.
+struct HexVisitor;
+
+impl<'de> Visitor<'de> for HexVisitor {
+    type Value = ByteTendril;
+
+    fn expecting(&self, f: &mut std_fmt::Formatter) -> std_fmt::Result {
+        write!(f, "a hex-encoded byte string")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        let v = v.as_bytes();
+        if v.len() % 2 != 0 {
+            return Err(E::custom("odd-length hex string"));
+        }
+        let mut t: ByteTendril = Tendril::new();
+        t.reserve((v.len() / 2) as u32);
+        for pair in v.chunks(2) {
+            let hi = from_hex_digit(pair[0]).ok_or_else(|| E::custom("invalid hex digit"))?;
+            let lo = from_hex_digit(pair[1]).ok_or_else(|| E::custom("invalid hex digit"))?;
+            t.push_slice(&[(hi << 4) | lo]);
+        }
+        Ok(t)
     }
 }
 
@@ -43,13 +213,54 @@ mod test {
     use super::*;
 
     #[test]
-    fn test_serialize_deserialize() {
-        let original = "test string";
-        let original_tendril = StrTendril::from_slice(original);
-        let encoded = serde_json::to_string(&original_tendril).unwrap();
+    fn str_round_trip() {
+        let original = StrTendril::from_slice("test string");
+        let encoded = serde_json::to_string(&original).unwrap();
         assert_eq!(encoded, r#""test string""#);
-        let decoded_tendril: StrTendril = serde_json::from_str(&encoded).unwrap();
-        assert_eq!(original_tendril, decoded_tendril);
-        assert_eq!(&decoded_tendril[..], original);
+        let decoded: StrTendril = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn byte_round_trip() {
+        // JSON is human-readable, so bytes become a hex string.
+        let original = ByteTendril::from_slice(b"\x00\x01\xFF");
+        let encoded = serde_json::to_string(&original).unwrap();
+        assert_eq!(encoded, r#""0001ff""#);
+        let decoded: ByteTendril = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn byte_hex_errors() {
+        assert!(serde_json::from_str::<ByteTendril>(r#""abc""#).is_err()); // odd length
+        assert!(serde_json::from_str::<ByteTendril>(r#""zz""#).is_err()); // bad digit
+    }
+
+    #[test]
+    fn send_tendril_round_trip() {
+        let original: SendTendril<fmt::UTF8> = StrTendril::from_slice("queued").into_send();
+        let encoded = serde_json::to_string(&original).unwrap();
+        assert_eq!(encoded, r#""queued""#);
+        let decoded: SendTendril<fmt::UTF8> = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(StrTendril::from(original), StrTendril::from(decoded));
+    }
+
+    #[test]
+    fn generic_format_dispatch() {
+        // The blanket impl covers any `SerdeFormat`, exercised here for
+        // both the string and byte formats through one code path.
+        fn round_trip<F>(t: Tendril<F>) -> Tendril<F>
+            where F: super::SerdeFormat,
+                  Tendril<F>: PartialEq + std::fmt::Debug,
+        {
+            let bytes = serde_json::to_vec(&t).unwrap();
+            serde_json::from_slice(&bytes).unwrap()
+        }
+
+        let s = StrTendril::from_slice("héllo");
+        assert_eq!(s, round_trip(s.clone()));
+        let b = ByteTendril::from_slice(b"\x00\xFF");
+        assert_eq!(b, round_trip(b.clone()));
     }
 }
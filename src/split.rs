@@ -0,0 +1,424 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Zero-copy splitting iterators for `StrTendril`.
+//!
+//! Every item these iterators yield is a real `StrTendril` produced
+//! with `subtendril`: items longer than the 8-byte inline limit point
+//! into the parent's buffer, shorter ones are stored inline. Building a
+//! word index therefore costs no per-token heap copy.
+
+use fmt;
+use tendril::Tendril;
+
+type StrTendril = Tendril<fmt::UTF8>;
+
+/// A pattern that `split` can match against a `StrTendril`.
+///
+/// Implemented for `char` and for `F: FnMut(char) -> bool`, mirroring
+/// the standard library's `str::split`.
+pub trait Pattern {
+    /// Find the next match at or after `from`, returning its byte range.
+    fn find(&mut self, haystack: &str, from: usize) -> Option<(usize, usize)>;
+}
+
+impl Pattern for char {
+    #[inline]
+    fn find(&mut self, haystack: &str, from: usize) -> Option<(usize, usize)> {
+        haystack[from..].find(*self).map(|i| {
+            let start = from + i;
+            (start, start + self.len_utf8())
+        })
+    }
+}
+
+impl<F> Pattern for F
+    where F: FnMut(char) -> bool,
+{
+    #[inline]
+    fn find(&mut self, haystack: &str, from: usize) -> Option<(usize, usize)> {
+        for (i, c) in haystack[from..].char_indices() {
+            if self(c) {
+                return Some((from + i, from + i + c.len_utf8()));
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over subtendrils separated by a `Pattern`.
+///
+/// Yielded by `StrTendril::split`.
+pub struct Split<P> {
+    parent: StrTendril,
+    pat: P,
+    pos: usize,
+    done: bool,
+}
+
+impl<P> Iterator for Split<P>
+    where P: Pattern,
+{
+    type Item = StrTendril;
+
+    #[inline]
+    fn next(&mut self) -> Option<StrTendril> {
+        if self.done {
+            return None;
+        }
+        let start = self.pos;
+        match self.pat.find(&self.parent, start) {
+            Some((m_start, m_end)) => {
+                self.pos = m_end;
+                Some(self.parent.subtendril(start as u32, (m_start - start) as u32))
+            }
+            None => {
+                self.done = true;
+                let len = self.parent.len32();
+                Some(self.parent.subtendril(start as u32, len - start as u32))
+            }
+        }
+    }
+}
+
+/// Iterator over at most `n` subtendrils separated by a `Pattern`.
+///
+/// Yielded by `StrTendril::splitn`.
+pub struct SplitN<P> {
+    inner: Split<P>,
+    left: usize,
+}
+
+impl<P> Iterator for SplitN<P>
+    where P: Pattern,
+{
+    type Item = StrTendril;
+
+    #[inline]
+    fn next(&mut self) -> Option<StrTendril> {
+        match self.left {
+            0 => None,
+            1 => {
+                self.left = 0;
+                if self.inner.done {
+                    return None;
+                }
+                self.inner.done = true;
+                let len = self.inner.parent.len32();
+                let start = self.inner.pos;
+                Some(self.inner.parent.subtendril(start as u32, len - start as u32))
+            }
+            _ => {
+                self.left -= 1;
+                self.inner.next()
+            }
+        }
+    }
+}
+
+/// Iterator over subtendrils separated by a `char`, from the back.
+///
+/// Yielded by `StrTendril::rsplit`.
+pub struct RSplit {
+    parent: StrTendril,
+    sep: char,
+    end: usize,
+    done: bool,
+}
+
+impl Iterator for RSplit {
+    type Item = StrTendril;
+
+    #[inline]
+    fn next(&mut self) -> Option<StrTendril> {
+        if self.done {
+            return None;
+        }
+        match self.parent[..self.end].rfind(self.sep) {
+            Some(i) => {
+                let start = i + self.sep.len_utf8();
+                let t = self.parent.subtendril(start as u32, (self.end - start) as u32);
+                self.end = i;
+                Some(t)
+            }
+            None => {
+                self.done = true;
+                Some(self.parent.subtendril(0, self.end as u32))
+            }
+        }
+    }
+}
+
+/// Iterator over the whitespace-separated words of a `StrTendril`.
+///
+/// Yielded by `StrTendril::split_whitespace`. Empty runs between
+/// separators are skipped, matching `str::split_whitespace`.
+pub struct SplitWhitespace {
+    parent: StrTendril,
+    pos: usize,
+}
+
+impl Iterator for SplitWhitespace {
+    type Item = StrTendril;
+
+    #[inline]
+    fn next(&mut self) -> Option<StrTendril> {
+        let s: &str = &self.parent;
+        let len = s.len();
+        // Skip leading whitespace.
+        let mut start = self.pos;
+        while start < len {
+            let c = s[start..].chars().next().unwrap();
+            if c.is_whitespace() {
+                start += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        if start >= len {
+            self.pos = len;
+            return None;
+        }
+        // Scan to the next whitespace char.
+        let mut end = start;
+        while end < len {
+            let c = s[end..].chars().next().unwrap();
+            if c.is_whitespace() {
+                break;
+            }
+            end += c.len_utf8();
+        }
+        self.pos = end;
+        Some(self.parent.subtendril(start as u32, (end - start) as u32))
+    }
+}
+
+impl Tendril<fmt::UTF8> {
+    /// Split on each match of `pat`, yielding shared subtendrils.
+    #[inline]
+    pub fn split<P: Pattern>(&self, pat: P) -> Split<P> {
+        Split {
+            parent: self.clone(),
+            pat: pat,
+            pos: 0,
+            done: false,
+        }
+    }
+
+    /// Split on `pat`, returning at most `n` subtendrils.
+    ///
+    /// The last item holds the unsplit remainder.
+    #[inline]
+    pub fn splitn<P: Pattern>(&self, n: usize, pat: P) -> SplitN<P> {
+        SplitN {
+            inner: self.split(pat),
+            left: n,
+        }
+    }
+
+    /// Split on each `sep`, yielding shared subtendrils from the back.
+    #[inline]
+    pub fn rsplit(&self, sep: char) -> RSplit {
+        RSplit {
+            parent: self.clone(),
+            sep: sep,
+            end: self.len32() as usize,
+            done: false,
+        }
+    }
+
+    /// Iterate over the whitespace-separated words as shared subtendrils.
+    #[inline]
+    pub fn split_whitespace(&self) -> SplitWhitespace {
+        SplitWhitespace {
+            parent: self.clone(),
+            pos: 0,
+        }
+    }
+}
+
+/// Iterator over overlapping windows of `n` characters.
+///
+/// Yielded by `StrTendril::char_ngrams`.
+pub struct CharNgrams {
+    parent: StrTendril,
+    /// Byte offsets of each character start, plus the total length.
+    bounds: Vec<u32>,
+    n: usize,
+    pos: usize,
+}
+
+impl Iterator for CharNgrams {
+    type Item = StrTendril;
+
+    #[inline]
+    fn next(&mut self) -> Option<StrTendril> {
+        // `bounds` has one entry per char plus a final length sentinel.
+        if self.n == 0 || self.pos + self.n >= self.bounds.len() {
+            return None;
+        }
+        let start = self.bounds[self.pos];
+        let end = self.bounds[self.pos + self.n];
+        self.pos += 1;
+        Some(self.parent.subtendril(start, end - start))
+    }
+}
+
+/// Iterator over overlapping windows of `n` whitespace-separated tokens.
+///
+/// Each window is a single subtendril spanning from the first token's
+/// start to the last token's end, so the original interior whitespace
+/// is preserved and the slice stays a contiguous view. Yielded by
+/// `StrTendril::ngrams`.
+pub struct Ngrams {
+    parent: StrTendril,
+    /// `(start, end)` byte spans of each token.
+    tokens: Vec<(u32, u32)>,
+    n: usize,
+    pos: usize,
+}
+
+impl Iterator for Ngrams {
+    type Item = StrTendril;
+
+    #[inline]
+    fn next(&mut self) -> Option<StrTendril> {
+        if self.n == 0 || self.pos + self.n > self.tokens.len() {
+            return None;
+        }
+        let start = self.tokens[self.pos].0;
+        let end = self.tokens[self.pos + self.n - 1].1;
+        self.pos += 1;
+        Some(self.parent.subtendril(start, end - start))
+    }
+}
+
+impl Tendril<fmt::UTF8> {
+    /// Iterate over overlapping windows of `n` characters as shared
+    /// subtendrils, advancing one character per step.
+    ///
+    /// The iterator is empty when `n` exceeds the character count.
+    pub fn char_ngrams(&self, n: usize) -> CharNgrams {
+        let s: &str = self;
+        let mut bounds: Vec<u32> = s.char_indices().map(|(i, _)| i as u32).collect();
+        bounds.push(s.len() as u32);
+        CharNgrams {
+            parent: self.clone(),
+            bounds: bounds,
+            n: n,
+            pos: 0,
+        }
+    }
+
+    /// Iterate over overlapping windows of `n` whitespace-separated
+    /// tokens, each a single shared subtendril spanning the window
+    /// (interior whitespace preserved), advancing one token per step.
+    ///
+    /// The iterator is empty when `n` exceeds the token count.
+    pub fn ngrams(&self, n: usize) -> Ngrams {
+        let s: &str = self;
+        let mut tokens: Vec<(u32, u32)> = Vec::new();
+        let mut start: Option<usize> = None;
+        for (i, c) in s.char_indices() {
+            if c.is_whitespace() {
+                if let Some(st) = start.take() {
+                    tokens.push((st as u32, i as u32));
+                }
+            } else if start.is_none() {
+                start = Some(i);
+            }
+        }
+        if let Some(st) = start {
+            tokens.push((st as u32, s.len() as u32));
+        }
+        Ngrams {
+            parent: self.clone(),
+            tokens: tokens,
+            n: n,
+            pos: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tendril::SliceExt;
+
+    #[test]
+    fn split_char() {
+        let t = "a,b,,c".to_tendril();
+        let v: Vec<_> = t.split(',').map(|s| s.to_string()).collect();
+        assert_eq!(vec!["a", "b", "", "c"], v);
+    }
+
+    #[test]
+    fn split_shares() {
+        let t = "alpha beta gamma".to_tendril();
+        for word in t.split(' ') {
+            assert!(word.is_shared_with(&t) || word.len32() <= 8);
+        }
+    }
+
+    #[test]
+    fn splitn_char() {
+        let t = "a,b,c,d".to_tendril();
+        let v: Vec<_> = t.splitn(2, ',').map(|s| s.to_string()).collect();
+        assert_eq!(vec!["a", "b,c,d"], v);
+    }
+
+    #[test]
+    fn rsplit_char() {
+        let t = "a,b,c".to_tendril();
+        let v: Vec<_> = t.rsplit(',').map(|s| s.to_string()).collect();
+        assert_eq!(vec!["c", "b", "a"], v);
+    }
+
+    #[test]
+    fn lines() {
+        let t = "one\ntwo\r\nthree".to_tendril();
+        let v: Vec<_> = t.lines().map(|s| s.to_string()).collect();
+        assert_eq!(vec!["one", "two", "three"], v);
+    }
+
+    #[test]
+    fn split_whitespace() {
+        let t = "  the  quick \tbrown\n".to_tendril();
+        let v: Vec<_> = t.split_whitespace().map(|s| s.to_string()).collect();
+        assert_eq!(vec!["the", "quick", "brown"], v);
+    }
+
+    #[test]
+    fn split_fn() {
+        let t = "a1b2c".to_tendril();
+        let v: Vec<_> = t.split(|c: char| c.is_numeric()).map(|s| s.to_string()).collect();
+        assert_eq!(vec!["a", "b", "c"], v);
+    }
+
+    #[test]
+    fn char_ngrams() {
+        let t = "abcd".to_tendril();
+        let v: Vec<_> = t.char_ngrams(2).map(|s| s.to_string()).collect();
+        assert_eq!(vec!["ab", "bc", "cd"], v);
+
+        // n larger than the char count yields nothing.
+        assert_eq!(0, "ab".to_tendril().char_ngrams(3).count());
+        // exact length is one window.
+        assert_eq!(vec!["ab"], "ab".to_tendril().char_ngrams(2)
+            .map(|s| s.to_string()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn token_ngrams() {
+        let t = "the  quick brown fox".to_tendril();
+        let v: Vec<_> = t.ngrams(2).map(|s| s.to_string()).collect();
+        // Interior whitespace is preserved within each window.
+        assert_eq!(vec!["the  quick", "quick brown", "brown fox"], v);
+
+        assert_eq!(0, "one two".to_tendril().ngrams(3).count());
+        assert_eq!(vec!["one two"], "one two".to_tendril().ngrams(2)
+            .map(|s| s.to_string()).collect::<Vec<_>>());
+    }
+}
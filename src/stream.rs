@@ -9,7 +9,7 @@
 use tendril::Tendril;
 use fmt;
 
-use std::{cmp, mem};
+use std::{cmp, char, mem};
 use std::borrow::Cow;
 
 use encoding::{self, EncodingRef, RawDecoder, DecoderTrap};
@@ -38,6 +38,58 @@ pub trait TendrilSink<F>
     fn error(&mut self, desc: Cow<'static, str>);
 }
 
+/// Convenience methods for pushing raw byte streams into a sink.
+///
+/// Each read lands in a freshly allocated `Tendril<fmt::Bytes>` filled
+/// through the uninitialized-capacity API and handed straight to
+/// `process`, so no intermediate `Vec` is allocated.
+#[cfg(feature = "std")]
+pub trait ByteTendrilSinkExt: TendrilSink<fmt::Bytes> {
+    /// Read all bytes from `r` into the sink, then finish the stream.
+    ///
+    /// Returns the total number of bytes read.
+    fn read_from<R>(&mut self, r: &mut R) -> ::std::io::Result<u64>
+        where R: ::std::io::Read, Self: Sized
+    {
+        use std::io::{self, Read};
+        const BUFFER_SIZE: u32 = 64 * 1024;
+
+        let mut total = 0u64;
+        loop {
+            let mut t: Tendril<fmt::Bytes> = Tendril::new();
+            unsafe {
+                t.push_uninitialized(BUFFER_SIZE);
+            }
+            let n = loop {
+                match r.read(&mut t) {
+                    Ok(n) => break n,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            };
+            if n == 0 {
+                break;
+            }
+            t.pop_back(BUFFER_SIZE - n as u32);
+            total += n as u64;
+            self.process(t);
+        }
+        self.finish();
+        Ok(total)
+    }
+
+    /// Read the contents of a file into the sink, then finish the stream.
+    fn from_file<P>(&mut self, path: P) -> ::std::io::Result<u64>
+        where P: AsRef<::std::path::Path>, Self: Sized
+    {
+        let mut file = ::std::fs::File::open(path)?;
+        self.read_from(&mut file)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Sink> ByteTendrilSinkExt for Sink where Sink: TendrilSink<fmt::Bytes> {}
+
 /// Incrementally validate a byte stream as UTF-8.
 ///
 /// This will copy as little as possible — only the characters that
@@ -226,9 +278,326 @@ impl<Sink> TendrilSink<fmt::Bytes> for Decoder<Sink>
     }
 }
 
+/// State of the self-contained UTF-8 decode machine.
+///
+/// `needed == 0` is the ground state, awaiting a lead byte. Otherwise a
+/// multi-byte sequence is in progress: `seen` continuation bytes have
+/// been accumulated into `code_point`, and the next continuation byte
+/// must fall in `lower..=upper` (the range is widened to `0x80..=0xBF`
+/// after the first continuation byte).
+struct Utf8State {
+    needed: u8,
+    seen: u8,
+    code_point: u32,
+    lower: u8,
+    upper: u8,
+}
+
+impl Utf8State {
+    #[inline]
+    fn ground() -> Utf8State {
+        Utf8State { needed: 0, seen: 0, code_point: 0, lower: 0x80, upper: 0xBF }
+    }
+
+    #[inline]
+    fn begin(&mut self, needed: u8, init: u32, lower: u8, upper: u8) {
+        self.needed = needed;
+        self.seen = 0;
+        self.code_point = init;
+        self.lower = lower;
+        self.upper = upper;
+    }
+}
+
+/// Incrementally decode a UTF-8 byte stream, replacing ill-formed input
+/// with `U+FFFD` by the WHATWG "maximal subpart" rule.
+///
+/// Unlike `Decoder`, this does not depend on the `encoding` crate: it
+/// runs a small state machine and buffers at most the four bytes of an
+/// incomplete sequence across chunk boundaries. On an invalid
+/// continuation byte it emits one replacement character for the maximal
+/// valid prefix and resumes decoding at the offending byte.
+pub struct LossyDecoder<Sink> {
+    state: Utf8State,
+    sink: Sink,
+}
+
+impl<Sink> LossyDecoder<Sink>
+    where Sink: TendrilSink<fmt::UTF8>,
+{
+    /// Create a new incremental lossy decoder.
+    #[inline]
+    pub fn new(sink: Sink) -> LossyDecoder<Sink> {
+        LossyDecoder {
+            state: Utf8State::ground(),
+            sink: sink,
+        }
+    }
+
+    /// Consume the decoder and obtain the sink.
+    #[inline]
+    pub fn into_sink(self) -> Sink {
+        self.sink
+    }
+
+    fn emit_char(&mut self, c: char) {
+        let mut t: Tendril<fmt::UTF8> = Tendril::new();
+        t.push_char(c);
+        self.sink.process(t);
+    }
+
+    /// Flush `out`, then report an error and emit one replacement char.
+    fn emit_replacement(&mut self, out: &mut Tendril<fmt::UTF8>) {
+        if out.len() > 0 {
+            let done = mem::replace(out, Tendril::new());
+            self.sink.process(done);
+        }
+        self.sink.error(Cow::Borrowed("invalid byte sequence(s)"));
+        self.emit_char('\u{fffd}');
+    }
+}
+
+impl<Sink> TendrilSink<fmt::Bytes> for LossyDecoder<Sink>
+    where Sink: TendrilSink<fmt::UTF8>,
+{
+    fn process(&mut self, t: Tendril<fmt::Bytes>) {
+        let bytes = &*t;
+        let mut out: Tendril<fmt::UTF8> = Tendril::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            if self.state.needed == 0 {
+                match b {
+                    0x00..=0x7F => unsafe {
+                        out.push_bytes_without_validating(&bytes[i..i + 1]);
+                    },
+                    0xC2..=0xDF => self.state.begin(1, (b & 0x1F) as u32, 0x80, 0xBF),
+                    0xE0 => self.state.begin(2, (b & 0x0F) as u32, 0xA0, 0xBF),
+                    0xE1..=0xEC | 0xEE..=0xEF =>
+                        self.state.begin(2, (b & 0x0F) as u32, 0x80, 0xBF),
+                    0xED => self.state.begin(2, (b & 0x0F) as u32, 0x80, 0x9F),
+                    0xF0 => self.state.begin(3, (b & 0x07) as u32, 0x90, 0xBF),
+                    0xF1..=0xF3 => self.state.begin(3, (b & 0x07) as u32, 0x80, 0xBF),
+                    0xF4 => self.state.begin(3, (b & 0x07) as u32, 0x80, 0x8F),
+                    _ => self.emit_replacement(&mut out),
+                }
+                i += 1;
+            } else if b >= self.state.lower && b <= self.state.upper {
+                self.state.lower = 0x80;
+                self.state.upper = 0xBF;
+                self.state.code_point = (self.state.code_point << 6) | (b & 0x3F) as u32;
+                self.state.seen += 1;
+                if self.state.seen == self.state.needed {
+                    let cp = self.state.code_point;
+                    self.state = Utf8State::ground();
+                    out.push_char(char::from_u32(cp)
+                        .expect("LossyDecoder: internal error"));
+                }
+                i += 1;
+            } else {
+                // Invalid continuation: emit one replacement for the
+                // maximal valid prefix and reprocess `b` as a lead byte.
+                self.state = Utf8State::ground();
+                self.emit_replacement(&mut out);
+            }
+        }
+        if out.len() > 0 {
+            self.sink.process(out);
+        }
+    }
+
+    #[inline]
+    fn finish(&mut self) {
+        if self.state.needed > 0 {
+            self.state = Utf8State::ground();
+            self.sink.error(Cow::Borrowed("incomplete byte sequence at end of stream"));
+            self.emit_char('\u{fffd}');
+        }
+        self.sink.finish();
+    }
+
+    #[inline]
+    fn error(&mut self, desc: Cow<'static, str>) {
+        self.sink.error(desc);
+    }
+}
+
+/// Encode a code point (possibly an unpaired surrogate) as generalized
+/// UTF-8 / WTF-8 and append it to `out`.
+fn push_wtf8(out: &mut Tendril<fmt::Bytes>, cp: u32) {
+    if cp < 0x80 {
+        out.push_slice(&[cp as u8]);
+    } else if cp < 0x800 {
+        out.push_slice(&[
+            0xC0 | (cp >> 6) as u8,
+            0x80 | (cp & 0x3F) as u8,
+        ]);
+    } else if cp < 0x10000 {
+        out.push_slice(&[
+            0xE0 | (cp >> 12) as u8,
+            0x80 | ((cp >> 6) & 0x3F) as u8,
+            0x80 | (cp & 0x3F) as u8,
+        ]);
+    } else {
+        out.push_slice(&[
+            0xF0 | (cp >> 18) as u8,
+            0x80 | ((cp >> 12) & 0x3F) as u8,
+            0x80 | ((cp >> 6) & 0x3F) as u8,
+            0x80 | (cp & 0x3F) as u8,
+        ]);
+    }
+}
+
+/// Incrementally decode a UTF-16 byte stream to WTF-8.
+///
+/// This is the natural producer for the `WTF8` format: ill-formed
+/// UTF-16 (such as Windows file names with unpaired surrogates) round
+/// trips losslessly. In strict mode each unpaired surrogate is replaced
+/// with `U+FFFD` instead, so the output is well-formed UTF-8.
+pub struct UTF16Decoder<Sink> {
+    big_endian: bool,
+    strict: bool,
+    /// A leftover byte when a chunk ended on an odd boundary.
+    odd_byte: Option<u8>,
+    /// A high surrogate awaiting its low half.
+    high: Option<u16>,
+    sink: Sink,
+}
+
+impl<Sink> UTF16Decoder<Sink>
+    where Sink: TendrilSink<fmt::WTF8>,
+{
+    /// Create a lossless (WTF-8) decoder for the given endianness.
+    #[inline]
+    pub fn new(big_endian: bool, sink: Sink) -> UTF16Decoder<Sink> {
+        UTF16Decoder {
+            big_endian: big_endian,
+            strict: false,
+            odd_byte: None,
+            high: None,
+            sink: sink,
+        }
+    }
+
+    /// Create a strict decoder that replaces unpaired surrogates with
+    /// `U+FFFD`.
+    #[inline]
+    pub fn new_strict(big_endian: bool, sink: Sink) -> UTF16Decoder<Sink> {
+        UTF16Decoder { strict: true, ..UTF16Decoder::new(big_endian, sink) }
+    }
+
+    /// Consume the decoder and obtain the sink.
+    #[inline]
+    pub fn into_sink(self) -> Sink {
+        self.sink
+    }
+
+    #[inline]
+    fn assemble(&self, first: u8, second: u8) -> u16 {
+        if self.big_endian {
+            ((first as u16) << 8) | (second as u16)
+        } else {
+            (first as u16) | ((second as u16) << 8)
+        }
+    }
+
+    /// Emit a (possibly unpaired) surrogate, honoring strict mode.
+    fn emit_surrogate(&mut self, out: &mut Tendril<fmt::Bytes>, cp: u32) {
+        self.sink.error(Cow::Borrowed("unpaired UTF-16 surrogate"));
+        if self.strict {
+            push_wtf8(out, 0xFFFD);
+        } else {
+            push_wtf8(out, cp);
+        }
+    }
+
+    fn unit(&mut self, out: &mut Tendril<fmt::Bytes>, u: u16) {
+        match u {
+            0xD800..=0xDBFF => {
+                if let Some(hi) = self.high.take() {
+                    self.emit_surrogate(out, hi as u32);
+                }
+                self.high = Some(u);
+            }
+            0xDC00..=0xDFFF => {
+                match self.high.take() {
+                    Some(hi) => {
+                        let cp = 0x10000
+                            + (((hi - 0xD800) as u32) << 10)
+                            + ((u - 0xDC00) as u32);
+                        push_wtf8(out, cp);
+                    }
+                    None => self.emit_surrogate(out, u as u32),
+                }
+            }
+            _ => {
+                if let Some(hi) = self.high.take() {
+                    self.emit_surrogate(out, hi as u32);
+                }
+                push_wtf8(out, u as u32);
+            }
+        }
+    }
+}
+
+impl<Sink> TendrilSink<fmt::Bytes> for UTF16Decoder<Sink>
+    where Sink: TendrilSink<fmt::WTF8>,
+{
+    fn process(&mut self, t: Tendril<fmt::Bytes>) {
+        let bytes: &[u8] = &t;
+        let mut out: Tendril<fmt::Bytes> = Tendril::new();
+
+        let mut i = 0;
+        if let Some(b0) = self.odd_byte.take() {
+            if bytes.is_empty() {
+                self.odd_byte = Some(b0);
+                return;
+            }
+            let u = self.assemble(b0, bytes[0]);
+            i = 1;
+            self.unit(&mut out, u);
+        }
+
+        while i + 2 <= bytes.len() {
+            let u = self.assemble(bytes[i], bytes[i + 1]);
+            i += 2;
+            self.unit(&mut out, u);
+        }
+        if i < bytes.len() {
+            self.odd_byte = Some(bytes[i]);
+        }
+
+        if out.len() > 0 {
+            let w = unsafe { out.reinterpret_without_validating::<fmt::WTF8>() };
+            self.sink.process(w);
+        }
+    }
+
+    fn finish(&mut self) {
+        let mut out: Tendril<fmt::Bytes> = Tendril::new();
+        if let Some(hi) = self.high.take() {
+            self.emit_surrogate(&mut out, hi as u32);
+        }
+        if self.odd_byte.take().is_some() {
+            self.sink.error(Cow::Borrowed("odd number of bytes in UTF-16 stream"));
+            push_wtf8(&mut out, 0xFFFD);
+        }
+        if out.len() > 0 {
+            let w = unsafe { out.reinterpret_without_validating::<fmt::WTF8>() };
+            self.sink.process(w);
+        }
+        self.sink.finish();
+    }
+
+    #[inline]
+    fn error(&mut self, desc: Cow<'static, str>) {
+        self.sink.error(desc);
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{TendrilSink, Decoder, UTF8Validator};
+    use super::{TendrilSink, Decoder, LossyDecoder, UTF8Validator, UTF16Decoder};
     use tendril::{Tendril, SliceExt};
     use fmt;
     use std::borrow::Cow;
@@ -305,6 +674,70 @@ mod test {
         check_validate(&[b"\xEA\x99"], &["\u{fffd}"], 1);
     }
 
+    fn check_lossy(input: &[&[u8]], expected: &str, errs: usize) {
+        let mut decoder = LossyDecoder::new(Accumulate::new());
+        for x in input {
+            decoder.process(x.to_tendril());
+        }
+        decoder.finish();
+
+        let Accumulate { tendrils, errors } = decoder.into_sink();
+        let mut out: Tendril<fmt::UTF8> = Tendril::new();
+        for t in &tendrils {
+            out.push_tendril(t);
+        }
+        assert_eq!(expected, &*out);
+        assert_eq!(errs, errors.len());
+    }
+
+    struct AccumulateBytes {
+        out: Tendril<fmt::Bytes>,
+        finished: bool,
+    }
+
+    impl TendrilSink<fmt::Bytes> for AccumulateBytes {
+        fn process(&mut self, t: Tendril<fmt::Bytes>) {
+            self.out.push_tendril(&t);
+        }
+
+        fn finish(&mut self) {
+            self.finished = true;
+        }
+
+        fn error(&mut self, _desc: Cow<'static, str>) {}
+    }
+
+    #[test]
+    fn read_from_bytes() {
+        use super::ByteTendrilSinkExt;
+
+        let mut sink = AccumulateBytes { out: Tendril::new(), finished: false };
+        let data = b"the quick brown fox";
+        let n = sink.read_from(&mut &data[..]).unwrap();
+        assert_eq!(data.len() as u64, n);
+        assert_eq!(&data[..], &*sink.out);
+        assert!(sink.finished);
+    }
+
+    #[test]
+    fn lossy_decode_utf8() {
+        check_lossy(&[], "", 0);
+        check_lossy(&[b"xyz"], "xyz", 0);
+        check_lossy(&[b"xy\xEA\x99\xAEz"], "xy\u{a66e}z", 0);
+
+        // Sequence split across chunk boundaries.
+        check_lossy(&[b"xy\xEA", b"\x99", b"\xAEz"], "xy\u{a66e}z", 0);
+        check_lossy(&[b"\xF0", b"\x9F", b"\x92", b"\xA9"], "\u{1f4a9}", 0);
+
+        // Invalid continuation: one replacement, resume at offending byte.
+        check_lossy(&[b"\xEA\x99xy"], "\u{fffd}xy", 1);
+        check_lossy(&[b"\xE0\x80"], "\u{fffd}\u{fffd}", 2); // overlong: 0x80 < 0xA0
+
+        // Incomplete sequence at end of stream.
+        check_lossy(&[b"ab\xEA\x99"], "ab\u{fffd}", 1);
+        check_lossy(&[b"\xC0"], "\u{fffd}", 1); // invalid lead
+    }
+
     fn check_decode(enc: EncodingRef, input: &[&[u8]], expected: &str, errs: usize) {
         let mut decoder = Decoder::new(enc, Accumulate::new());
         for x in input {
@@ -368,6 +801,67 @@ mod test {
         check_decode(enc::KOI8_U, &[b"\xfc\xce", b"", b"\xc5\xd2\xc7", b"\xc9\xd1", b""], "Энергия", 0);
     }
 
+    struct AccumulateWtf8 {
+        bytes: Tendril<fmt::Bytes>,
+        errors: usize,
+    }
+
+    impl AccumulateWtf8 {
+        fn new() -> AccumulateWtf8 {
+            AccumulateWtf8 { bytes: Tendril::new(), errors: 0 }
+        }
+    }
+
+    impl TendrilSink<fmt::WTF8> for AccumulateWtf8 {
+        fn process(&mut self, t: Tendril<fmt::WTF8>) {
+            let b = unsafe { t.reinterpret_without_validating::<fmt::Bytes>() };
+            self.bytes.push_tendril(&b);
+        }
+
+        fn error(&mut self, _desc: Cow<'static, str>) {
+            self.errors += 1;
+        }
+    }
+
+    fn decode_utf16(big_endian: bool, input: &[&[u8]]) -> (Vec<u8>, usize) {
+        let mut d = UTF16Decoder::new(big_endian, AccumulateWtf8::new());
+        for x in input {
+            d.process(x.to_tendril());
+        }
+        d.finish();
+        let acc = d.into_sink();
+        ((&*acc.bytes).to_vec(), acc.errors)
+    }
+
+    #[test]
+    fn decode_utf16_basic() {
+        // "hi" little-endian, split across chunks on an odd boundary.
+        let (out, errs) = decode_utf16(false, &[b"h\x00i", b"\x00"]);
+        assert_eq!(b"hi".to_vec(), out);
+        assert_eq!(0, errs);
+
+        // "hi" big-endian.
+        let (out, errs) = decode_utf16(true, &[b"\x00h\x00i"]);
+        assert_eq!(b"hi".to_vec(), out);
+        assert_eq!(0, errs);
+    }
+
+    #[test]
+    fn decode_utf16_astral() {
+        // U+1F600, little-endian surrogate pair D83D DE00.
+        let (out, errs) = decode_utf16(false, &[b"\x3d\xd8", b"\x00\xde"]);
+        assert_eq!(b"\xF0\x9F\x98\x80".to_vec(), out);
+        assert_eq!(0, errs);
+    }
+
+    #[test]
+    fn decode_utf16_lone_surrogate() {
+        // Lone high surrogate D800 (LE) round-trips as WTF-8 ED A0 80.
+        let (out, errs) = decode_utf16(false, &[b"\x00\xd8"]);
+        assert_eq!(b"\xED\xA0\x80".to_vec(), out);
+        assert_eq!(1, errs);
+    }
+
     #[test]
     fn decode_windows_949() {
         check_decode(enc::WINDOWS_949, &[], "", 0);
@@ -0,0 +1,346 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A compact, human-readable structured text format backed by tendrils.
+//!
+//! The grammar is deliberately small: a scalar is a run of restricted
+//! ASCII (alphanumerics plus ``-_./:``), `{ key = value; ... }` is a
+//! dict, `[ a; b ]` is a list, and a bare run of whitespace-separated
+//! scalars is a sequence. Every scalar in a parsed [`Value`] is a
+//! `subtendril` of the source buffer: a scalar longer than the 8-byte
+//! inline limit shares the source buffer and reports `is_shared_with`
+//! the input, while a shorter one is a small inline copy. Encoding
+//! assembles output with `push_tendril`/`push_slice`, reusing the
+//! shared-append fast path.
+
+use fmt;
+use tendril::Tendril;
+
+type StrTendril = Tendril<fmt::UTF8>;
+
+/// Maximum dict/list nesting depth. `value()` recurses into `dict()`/
+/// `list()` and back, so unbounded input like `[[[[...` would otherwise
+/// blow the stack; this caps it well short of that on any target.
+const MAX_DEPTH: usize = 128;
+
+/// A parsed value in the structured text format.
+pub enum Value {
+    /// A single restricted-ASCII scalar.
+    Scalar(StrTendril),
+    /// A `{ key = value; ... }` mapping.
+    Dict(Vec<(StrTendril, Value)>),
+    /// A `[ a; b; ... ]` list.
+    List(Vec<Value>),
+    /// A run of whitespace-separated scalars. Never nested.
+    Sequence(Vec<StrTendril>),
+}
+
+/// An error encountered while parsing.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// A byte outside the allowed scalar set appeared where a scalar
+    /// was expected. The parser reports that case as `Unexpected(byte)`
+    /// instead, so `Value::parse` does not return this variant.
+    BadScalar,
+    /// The input ended before a value was complete.
+    UnexpectedEof,
+    /// A structural character was missing or misplaced.
+    Unexpected(u8),
+    /// Dicts/lists were nested deeper than [`MAX_DEPTH`].
+    TooDeep,
+    /// A bare scalar sequence appeared as a dict/list item. Sequences are
+    /// only a top-level production; see [`Value::Sequence`].
+    NestedSequence,
+}
+
+#[inline]
+fn is_scalar_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'/' | b':')
+}
+
+struct Parser<'a> {
+    src: &'a StrTendril,
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    #[inline]
+    fn skip_ws(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn scalar(&mut self) -> Result<StrTendril, ParseError> {
+        let start = self.pos;
+        while self.pos < self.bytes.len() && is_scalar_byte(self.bytes[self.pos]) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return match self.peek() {
+                Some(b) => Err(ParseError::BadScalar.or_byte(b)),
+                None => Err(ParseError::UnexpectedEof),
+            };
+        }
+        Ok(self.src.subtendril(start as u32, (self.pos - start) as u32))
+    }
+
+    /// Parse a value, allowing a bare sequence at this position.
+    ///
+    /// Tracks recursion depth across the `value`/`dict`/`list` cycle and
+    /// bails with `TooDeep` rather than overflowing the stack.
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            self.depth -= 1;
+            return Err(ParseError::TooDeep);
+        }
+        let result = self.value_inner();
+        self.depth -= 1;
+        result
+    }
+
+    fn value_inner(&mut self) -> Result<Value, ParseError> {
+        self.skip_ws();
+        match self.peek() {
+            Some(b'{') => self.dict(),
+            Some(b'[') => self.list(),
+            Some(_) => {
+                // One or more whitespace-separated scalars.
+                let mut scalars = vec![self.scalar()?];
+                loop {
+                    let save = self.pos;
+                    self.skip_ws();
+                    match self.peek() {
+                        Some(b) if is_scalar_byte(b) => scalars.push(self.scalar()?),
+                        _ => {
+                            self.pos = save;
+                            break;
+                        }
+                    }
+                }
+                if scalars.len() == 1 {
+                    Ok(Value::Scalar(scalars.pop().unwrap()))
+                } else {
+                    Ok(Value::Sequence(scalars))
+                }
+            }
+            None => Err(ParseError::UnexpectedEof),
+        }
+    }
+
+    /// Parse a value in dict/list item position, where a bare sequence
+    /// isn't allowed (`Value::Sequence` is never nested).
+    fn nested_value(&mut self) -> Result<Value, ParseError> {
+        match self.value()? {
+            Value::Sequence(_) => Err(ParseError::NestedSequence),
+            v => Ok(v),
+        }
+    }
+
+    fn dict(&mut self) -> Result<Value, ParseError> {
+        self.expect(b'{')?;
+        let mut entries = Vec::new();
+        loop {
+            self.skip_ws();
+            match self.peek() {
+                Some(b'}') => { self.pos += 1; break; }
+                None => return Err(ParseError::UnexpectedEof),
+                _ => {}
+            }
+            let key = self.scalar()?;
+            self.skip_ws();
+            self.expect(b'=')?;
+            let val = self.nested_value()?;
+            entries.push((key, val));
+            self.skip_ws();
+            match self.peek() {
+                Some(b';') => self.pos += 1,
+                Some(b'}') => { self.pos += 1; break; }
+                Some(b) => return Err(ParseError::Unexpected(b)),
+                None => return Err(ParseError::UnexpectedEof),
+            }
+        }
+        Ok(Value::Dict(entries))
+    }
+
+    fn list(&mut self) -> Result<Value, ParseError> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_ws();
+            match self.peek() {
+                Some(b']') => { self.pos += 1; break; }
+                None => return Err(ParseError::UnexpectedEof),
+                _ => {}
+            }
+            items.push(self.nested_value()?);
+            self.skip_ws();
+            match self.peek() {
+                Some(b';') => self.pos += 1,
+                Some(b']') => { self.pos += 1; break; }
+                Some(b) => return Err(ParseError::Unexpected(b)),
+                None => return Err(ParseError::UnexpectedEof),
+            }
+        }
+        Ok(Value::List(items))
+    }
+
+    #[inline]
+    fn expect(&mut self, b: u8) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(x) if x == b => { self.pos += 1; Ok(()) }
+            Some(x) => Err(ParseError::Unexpected(x)),
+            None => Err(ParseError::UnexpectedEof),
+        }
+    }
+}
+
+impl ParseError {
+    #[inline]
+    fn or_byte(self, b: u8) -> ParseError {
+        match self {
+            ParseError::BadScalar => ParseError::Unexpected(b),
+            other => other,
+        }
+    }
+}
+
+impl Value {
+    /// Parse `input`, producing a tree whose scalars are subtendrils of
+    /// `input` (see the module docs for when they share its buffer).
+    pub fn parse(input: &StrTendril) -> Result<Value, ParseError> {
+        let mut p = Parser { src: input, bytes: input.as_bytes(), pos: 0, depth: 0 };
+        let v = p.value()?;
+        p.skip_ws();
+        match p.peek() {
+            None => Ok(v),
+            Some(b) => Err(ParseError::Unexpected(b)),
+        }
+    }
+
+    /// Append the textual encoding of this value onto `out`.
+    pub fn encode(&self, out: &mut StrTendril) {
+        match *self {
+            Value::Scalar(ref s) => out.push_tendril(s),
+            Value::Sequence(ref scalars) => {
+                for (i, s) in scalars.iter().enumerate() {
+                    if i != 0 {
+                        out.push_slice(" ");
+                    }
+                    out.push_tendril(s);
+                }
+            }
+            Value::List(ref items) => {
+                out.push_slice("[");
+                for (i, item) in items.iter().enumerate() {
+                    if i != 0 {
+                        out.push_slice("; ");
+                    }
+                    item.encode(out);
+                }
+                out.push_slice("]");
+            }
+            Value::Dict(ref entries) => {
+                out.push_slice("{");
+                for (i, &(ref k, ref v)) in entries.iter().enumerate() {
+                    if i != 0 {
+                        out.push_slice("; ");
+                    }
+                    out.push_tendril(k);
+                    out.push_slice(" = ");
+                    v.encode(out);
+                }
+                out.push_slice("}");
+            }
+        }
+    }
+
+    /// Encode this value into a fresh `StrTendril`.
+    pub fn to_tendril(&self) -> StrTendril {
+        let mut out = Tendril::new();
+        self.encode(&mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ParseError, Value};
+    use tendril::SliceExt;
+
+    #[test]
+    fn scalars_share_source() {
+        let src = "{ alpha = one-two-three; beta = [ x; y ] }".to_tendril();
+        let v = Value::parse(&src).unwrap();
+        match v {
+            Value::Dict(entries) => {
+                assert_eq!(2, entries.len());
+                // A scalar long enough to escape the inline bound shares
+                // the source buffer.
+                match entries[0] {
+                    (ref k, Value::Scalar(ref leaf)) => {
+                        assert_eq!("alpha", &**k);
+                        assert_eq!("one-two-three", &**leaf);
+                        assert!(leaf.is_shared_with(&src));
+                    }
+                    _ => panic!("bad first entry"),
+                }
+            }
+            _ => panic!("expected dict"),
+        }
+    }
+
+    #[test]
+    fn sequence() {
+        let src = "a b c".to_tendril();
+        match Value::parse(&src).unwrap() {
+            Value::Sequence(v) => assert_eq!(3, v.len()),
+            _ => panic!("expected sequence"),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let src = "{x = [a; b]; y = [p; q]}".to_tendril();
+        let v = Value::parse(&src).unwrap();
+        let encoded = v.to_tendril();
+        // Re-parsing the encoding yields the same shape.
+        let v2 = Value::parse(&encoded).unwrap();
+        assert_eq!(encoded.to_string(), v2.to_tendril().to_string());
+    }
+
+    #[test]
+    fn rejects_bad_scalar() {
+        let src = "{ k = a!b }".to_tendril();
+        assert_eq!(Err(ParseError::Unexpected(b'!')), Value::parse(&src));
+    }
+
+    #[test]
+    fn rejects_nested_sequence() {
+        // `Value::Sequence` is documented as "never nested" -- a bare
+        // scalar run is only a valid production at the top level.
+        let src = "[ a b; c ]".to_tendril();
+        assert_eq!(Err(ParseError::NestedSequence), Value::parse(&src));
+    }
+
+    #[test]
+    fn rejects_excessive_nesting() {
+        let mut text = String::new();
+        for _ in 0..10_000 {
+            text.push('[');
+        }
+        let src = text.to_tendril();
+        assert_eq!(Err(ParseError::TooDeep), Value::parse(&src));
+    }
+}
@@ -4,19 +4,29 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::{ptr, mem, intrinsics, hash, str, u32, io, slice, cmp};
-use std::borrow::{Borrow, Cow};
-use std::marker::PhantomData;
-use std::cell::Cell;
-use std::ops::{Deref, DerefMut};
-use std::iter::FromIterator;
-use std::io::Write;
-use std::default::Default;
-use std::cmp::Ordering;
-use std::fmt as strfmt;
+use core::{ptr, mem, intrinsics, hash, str, slice, cmp, char};
+use core::borrow::Borrow;
+use core::marker::PhantomData;
+use core::cell::Cell;
+use core::ops::{Deref, DerefMut};
+use core::iter::FromIterator;
+use core::default::Default;
+use core::cmp::Ordering;
+use core::fmt as strfmt;
+use core::sync::atomic::{self, AtomicUsize};
+
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::borrow::Cow;
+
+#[cfg(feature = "std")]
+use std::io::{self, Write};
 
 use encoding::{self, EncodingRef, DecoderTrap, EncoderTrap};
 
+use futf::{self, Meaning};
+
 use buf32::{self, Buf32};
 use fmt::{self, Slice};
 use fmt::imp::Fixup;
@@ -42,7 +52,9 @@ fn inline_tag(len: u32) -> NonZero<usize> {
 
 #[repr(packed)]
 struct Header {
-    refcount: Cell<usize>,
+    // Unconditionally atomic for every `Tendril<F>`, plain or `Send`-ed; see
+    // the `Tendril` struct doc for why there's no non-atomic fast path yet.
+    refcount: AtomicUsize,
     cap: u32,
 }
 
@@ -50,7 +62,7 @@ impl Header {
     #[inline(always)]
     unsafe fn new() -> Header {
         Header {
-            refcount: Cell::new(1),
+            refcount: AtomicUsize::new(1),
             cap: mem::uninitialized(),
         }
     }
@@ -70,10 +82,20 @@ pub enum SubtendrilError {
 /// if necessary. Further mutations occur in-place until the string becomes
 /// shared, e.g. with `clone()` or `subtendril()`.
 ///
-/// Buffer sharing is accomplished through thread-local (non-atomic) reference
-/// counting, which has very low overhead. The Rust type system will prevent
-/// you at compile time from sending a `Tendril` between threads. We plan to
-/// relax this restriction in the future; see `README.md`.
+/// Buffer sharing is accomplished through reference counting. `Header::refcount`
+/// is unconditionally an `AtomicUsize`: every `Tendril<F>` pays for an atomic
+/// RMW on each incref/decref, even though a plain `Tendril` has no
+/// `Sync`/`Send` impl and in practice is only ever touched from one thread at
+/// a time. That's a deliberate, known trade-off rather than an oversight --
+/// giving the common single-threaded case back a non-atomic `Cell<usize>`
+/// fast path (atomics only for buffers that actually cross threads via
+/// `SendTendril`) would mean threading a refcount-policy type parameter
+/// through `Buf32`, `Header`, and every representation-switching method here,
+/// which is a real scope change, not a drop-in fix; it's tracked as a
+/// follow-up rather than silently reintroduced. The
+/// Rust type system will prevent you at compile time from sending a
+/// `Tendril` between threads directly; convert through `into_send` /
+/// `SendTendril` to hand one to another thread.
 ///
 /// Whereas `String` allocates in the heap for any non-empty string, `Tendril`
 /// can store small strings (up to 8 bytes) in-line, without a heap allocation.
@@ -87,6 +109,10 @@ pub enum SubtendrilError {
 ///
 /// The maximum length of a `Tendril` is 4 GB. The library will panic if
 /// you attempt to go over the limit.
+///
+/// Heap storage always goes through `buf32::Global`; `Tendril<F>` takes
+/// only the one type parameter `F`, so a custom-allocator `buf32::Allocator`
+/// impl (e.g. for an enclave or embedded target) isn't reachable from here.
 #[cfg_attr(feature = "unstable", unsafe_no_drop_flag)]
 #[repr(packed)]
 pub struct Tendril<F>
@@ -134,11 +160,13 @@ impl<F> Drop for Tendril<F>
             let (buf, shared, _) = self.assume_buf();
             if shared {
                 let header = self.header();
-                let refcount = (*header).refcount.get() - 1;
-                if refcount == 0 {
+                // Release so earlier writes through this handle happen-before
+                // the buffer is freed by whichever thread drops the last
+                // reference; acquire-fence before `destroy` so that thread
+                // observes all of them, matching `bytes::Bytes`'s discipline.
+                if (*header).refcount.fetch_sub(1, atomic::Ordering::Release) == 1 {
+                    atomic::fence(atomic::Ordering::Acquire);
                     buf.destroy();
-                } else {
-                    (*header).refcount.set(refcount);
                 }
             } else {
                 buf.destroy();
@@ -796,8 +824,8 @@ impl<F> Tendril<F>
     #[inline]
     unsafe fn incref(&self) {
         let header = self.header();
-        let refcount = (*header).refcount.get().checked_add(1).expect(OFLOW);
-        (*header).refcount.set(refcount);
+        let prev = (*header).refcount.fetch_add(1, atomic::Ordering::Relaxed);
+        prev.checked_add(1).expect(OFLOW);
     }
 
     #[inline]
@@ -843,6 +871,7 @@ impl<F> Tendril<F>
             ptr: header,
             len: offset + self.len32(),
             cap: cap,
+            marker: PhantomData,
         }, shared, offset)
     }
 
@@ -1011,6 +1040,82 @@ impl<F> Tendril<F>
         }
     }
 
+    /// Remove and return the maximal run of characters at the front of the
+    /// `Tendril` which all satisfy `pred`.
+    ///
+    /// Returns an empty `Tendril` if the first character fails the predicate
+    /// or the string is empty. The returned piece is a subtendril: it shares
+    /// the backing buffer unless it is short enough to be stored inline.
+    #[inline]
+    pub fn pop_front_while<'a, P>(&'a mut self, mut pred: P) -> Tendril<F>
+        where P: FnMut(char) -> bool,
+    {
+        let split = {
+            let mut chars = unsafe { F::char_indices(self.as_byte_slice()) };
+            loop {
+                match chars.next() {
+                    Some((idx, ch)) if !pred(ch) => break idx,
+                    Some(_) => {}
+                    None => break self.len() as usize,
+                }
+            }
+        };
+        unsafe {
+            let t = self.unsafe_subtendril(0, split as u32);
+            self.unsafe_pop_front(split as u32);
+            t
+        }
+    }
+
+    /// Remove and return the maximal run of characters at the front of the
+    /// `Tendril` which all *fail* `pred`.
+    ///
+    /// This is the complement of [`pop_front_while`](#method.pop_front_while).
+    #[inline]
+    pub fn pop_front_until<'a, P>(&'a mut self, mut pred: P) -> Tendril<F>
+        where P: FnMut(char) -> bool,
+    {
+        self.pop_front_while(move |c| !pred(c))
+    }
+
+    /// Consume the `Tendril`, yielding `(run, class)` pairs by repeatedly
+    /// applying [`pop_front_char_run`](#method.pop_front_char_run).
+    ///
+    /// Each run is a zero-copy subtendril of the original buffer, which is
+    /// what a hand-rolled lexer wants when splitting input into tokens.
+    #[inline]
+    pub fn char_runs<C, R>(self, classify: C) -> CharRuns<F, C, R>
+        where C: FnMut(char) -> R,
+              R: PartialEq,
+    {
+        CharRuns { rest: self, classify: classify, _marker: PhantomData }
+    }
+
+    /// Split on each occurrence of `sep`, yielding shared subtendrils.
+    ///
+    /// Works for any character format, splitting on real code-point
+    /// edges. A trailing `sep` yields a final empty subtendril.
+    #[inline]
+    pub fn split_char(&self, sep: char) -> SplitChar<F> {
+        SplitChar { parent: self.clone(), sep: sep, pos: 0, done: false }
+    }
+
+    /// Split into maximal runs of characters sharing the same `pred`
+    /// value, yielding shared subtendrils.
+    #[inline]
+    pub fn split_char_run<P>(&self, pred: P) -> SplitCharRun<F, P>
+        where P: FnMut(char) -> bool,
+    {
+        SplitCharRun { parent: self.clone(), pred: pred, pos: 0, done: false }
+    }
+
+    /// Iterate over the lines as shared subtendrils, without their
+    /// terminating `\n` or `\r\n`.
+    #[inline]
+    pub fn lines(&self) -> Lines<F> {
+        Lines { parent: self.clone(), pos: 0 }
+    }
+
     /// Push a character, if it can be represented in this format.
     #[inline]
     pub fn try_push_char(&mut self, c: char) -> Result<(), ()> {
@@ -1020,11 +1125,171 @@ impl<F> Tendril<F>
     }
 }
 
+/// Iterator of classified character runs produced by
+/// [`Tendril::char_runs`](struct.Tendril.html#method.char_runs).
+///
+/// Each yielded run is a zero-copy subtendril sharing the original buffer.
+pub struct CharRuns<F, C, R>
+    where F: for<'a> fmt::CharFormat<'a>,
+          C: FnMut(char) -> R,
+          R: PartialEq,
+{
+    rest: Tendril<F>,
+    classify: C,
+    _marker: PhantomData<R>,
+}
+
+impl<F, C, R> Iterator for CharRuns<F, C, R>
+    where F: for<'a> fmt::CharFormat<'a>,
+          C: FnMut(char) -> R,
+          R: PartialEq,
+{
+    type Item = (Tendril<F>, R);
+
+    #[inline]
+    fn next(&mut self) -> Option<(Tendril<F>, R)> {
+        self.rest.pop_front_char_run(&mut self.classify)
+    }
+}
+
+/// Iterator of subtendrils separated by a character, produced by
+/// [`Tendril::split_char`](struct.Tendril.html#method.split_char).
+pub struct SplitChar<F>
+    where F: for<'a> fmt::CharFormat<'a>,
+{
+    parent: Tendril<F>,
+    sep: char,
+    pos: u32,
+    done: bool,
+}
+
+impl<F> Iterator for SplitChar<F>
+    where F: for<'a> fmt::CharFormat<'a>,
+{
+    type Item = Tendril<F>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Tendril<F>> {
+        if self.done {
+            return None;
+        }
+        let start = self.pos;
+        let total = self.parent.len32();
+        let mut it = unsafe {
+            F::char_indices(&self.parent.as_byte_slice()[start as usize..])
+        };
+        while let Some((i, c)) = it.next() {
+            if c == self.sep {
+                let m_start = start + i as u32;
+                // The next char's offset marks the end of `sep`.
+                self.pos = it.next()
+                    .map_or(total, |(j, _)| start + j as u32);
+                return Some(self.parent.subtendril(start, m_start - start));
+            }
+        }
+        self.done = true;
+        Some(self.parent.subtendril(start, total - start))
+    }
+}
+
+/// Iterator of same-class character runs, produced by
+/// [`Tendril::split_char_run`](struct.Tendril.html#method.split_char_run).
+pub struct SplitCharRun<F, P>
+    where F: for<'a> fmt::CharFormat<'a>,
+          P: FnMut(char) -> bool,
+{
+    parent: Tendril<F>,
+    pred: P,
+    pos: u32,
+    done: bool,
+}
+
+impl<F, P> Iterator for SplitCharRun<F, P>
+    where F: for<'a> fmt::CharFormat<'a>,
+          P: FnMut(char) -> bool,
+{
+    type Item = Tendril<F>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Tendril<F>> {
+        if self.done {
+            return None;
+        }
+        let start = self.pos;
+        let total = self.parent.len32();
+        let mut it = unsafe {
+            F::char_indices(&self.parent.as_byte_slice()[start as usize..])
+        };
+        let class = match it.next() {
+            Some((_, c)) => (self.pred)(c),
+            None => {
+                self.done = true;
+                return None;
+            }
+        };
+        let mut split = total;
+        for (i, c) in it {
+            if (self.pred)(c) != class {
+                split = start + i as u32;
+                break;
+            }
+        }
+        self.pos = split;
+        if split == total {
+            self.done = true;
+        }
+        Some(self.parent.subtendril(start, split - start))
+    }
+}
+
+/// Iterator of lines as shared subtendrils, produced by
+/// [`Tendril::lines`](struct.Tendril.html#method.lines).
+pub struct Lines<F>
+    where F: for<'a> fmt::CharFormat<'a>,
+{
+    parent: Tendril<F>,
+    pos: u32,
+}
+
+impl<F> Iterator for Lines<F>
+    where F: for<'a> fmt::CharFormat<'a>,
+{
+    type Item = Tendril<F>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Tendril<F>> {
+        let total = self.parent.len32();
+        if self.pos >= total {
+            return None;
+        }
+        let start = self.pos;
+        let mut it = unsafe {
+            F::char_indices(&self.parent.as_byte_slice()[start as usize..])
+        };
+        while let Some((i, c)) = it.next() {
+            if c == '\n' {
+                let nl = start + i as u32;
+                self.pos = it.next().map_or(total, |(j, _)| start + j as u32);
+                // Trim a preceding '\r' so "\r\n" is handled like std.
+                let mut end = nl;
+                if end > start && self.parent.as_byte_slice()[(end - 1) as usize] == b'\r' {
+                    end -= 1;
+                }
+                return Some(self.parent.subtendril(start, end - start));
+            }
+        }
+        self.pos = total;
+        Some(self.parent.subtendril(start, total - start))
+    }
+}
+
 /// Extension trait for `io::Read`.
+#[cfg(feature = "std")]
 pub trait ReadExt: io::Read {
     fn read_to_tendril(&mut self, buf: &mut Tendril<fmt::Bytes>) -> io::Result<usize>;
 }
 
+#[cfg(feature = "std")]
 impl<T> ReadExt for T
     where T: io::Read
 {
@@ -1067,6 +1332,7 @@ impl<T> ReadExt for T
     }
 }
 
+#[cfg(feature = "std")]
 impl io::Write for Tendril<fmt::Bytes> {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
@@ -1116,6 +1382,29 @@ impl Tendril<fmt::Bytes> {
         encoding.decode_to(&*self, trap, &mut ret).map(|_| ret)
     }
 
+    /// Decode into UTF-8, auto-detecting the encoding from a leading
+    /// byte-order mark.
+    ///
+    /// A UTF-8 (`EF BB BF`), UTF-16LE (`FF FE`) or UTF-16BE (`FE FF`) BOM
+    /// selects the matching codec and is stripped from the output;
+    /// anything else is decoded as UTF-8. Short inputs and a bare `FF`
+    /// are treated as UTF-8 rather than a truncated BOM.
+    pub fn decode_auto(&self, trap: DecoderTrap)
+        -> Result<Tendril<fmt::UTF8>, Cow<'static, str>>
+    {
+        let bytes: &[u8] = &*self;
+        let (enc, bom): (EncodingRef, u32) = if bytes.len() >= 3 && &bytes[..3] == b"\xEF\xBB\xBF" {
+            (encoding::all::UTF_8, 3)
+        } else if bytes.len() >= 2 && &bytes[..2] == b"\xFF\xFE" {
+            (encoding::all::UTF_16LE, 2)
+        } else if bytes.len() >= 2 && &bytes[..2] == b"\xFE\xFF" {
+            (encoding::all::UTF_16BE, 2)
+        } else {
+            (encoding::all::UTF_8, 0)
+        };
+        self.subtendril(bom, self.len32() - bom).decode(enc, trap)
+    }
+
     /// Push "uninitialized bytes" onto the end.
     ///
     /// Really, this grows the tendril without writing anything to the new area.
@@ -1133,6 +1422,53 @@ impl Tendril<fmt::Bytes> {
             self.len = new_len;
         }
     }
+
+    /// Read up to `n` bytes from `r` directly onto the end of the tendril.
+    ///
+    /// Reserves `n` bytes of uninitialized tail, reads into it once, and
+    /// truncates back to exactly the number of bytes read. No intermediate
+    /// buffer is allocated. Returns the number of bytes appended.
+    #[cfg(feature = "std")]
+    pub fn read_from<R>(&mut self, r: &mut R, n: u32) -> io::Result<u32>
+        where R: io::Read,
+    {
+        let start = self.len32();
+        unsafe {
+            self.push_uninitialized(n);
+        }
+        let result = r.read(&mut self[start as usize..]);
+        let read = match result {
+            Ok(k) => k as u32,
+            Err(e) => {
+                self.pop_back(n);
+                return Err(e);
+            }
+        };
+        self.pop_back(n - read);
+        Ok(read)
+    }
+
+    /// Read from `r` until EOF, appending everything onto the tendril.
+    ///
+    /// Returns the total number of bytes appended, growing the reserved
+    /// tail geometrically so large sources need only a handful of reads.
+    #[cfg(feature = "std")]
+    pub fn read_to_end<R>(&mut self, r: &mut R) -> io::Result<u32>
+        where R: io::Read,
+    {
+        let start = self.len32();
+        let mut chunk = buf32::MIN_CAP;
+        loop {
+            let n = self.read_from(r, chunk)?;
+            if n == 0 {
+                break;
+            }
+            if n == chunk && chunk < (1 << 16) {
+                chunk *= 2;
+            }
+        }
+        Ok(self.len32() - start)
+    }
 }
 
 impl strfmt::Display for Tendril<fmt::UTF8> {
@@ -1215,13 +1551,96 @@ impl Tendril<fmt::UTF8> {
     /// Helper for the `format_tendril!` macro.
     #[inline]
     pub fn format(args: strfmt::Arguments) -> Tendril<fmt::UTF8> {
-        use std::fmt::Write;
+        use core::fmt::Write;
         let mut output: Tendril<fmt::UTF8> = Tendril::new();
         let _ = write!(&mut output, "{}", args);
         output
     }
 }
 
+impl Tendril<fmt::WTF8> {
+    /// Iterate over the code points, including unpaired surrogates.
+    ///
+    /// Well-formed scalar values are yielded as their code point; an
+    /// unpaired surrogate is yielded as its surrogate code point in the
+    /// range `0xD800..=0xDFFF`, which cannot occur in well-formed UTF-8.
+    #[inline]
+    pub fn code_points(&self) -> CodePoints {
+        CodePoints {
+            bytes: self.as_byte_slice(),
+            pos: 0,
+        }
+    }
+
+    /// Encode as potentially ill-formed UTF-16.
+    ///
+    /// Unpaired surrogates are preserved as lone UTF-16 code units, so the
+    /// result round-trips back through `stream::UTF16Decoder`.
+    pub fn to_ill_formed_utf16(&self) -> Vec<u16> {
+        let mut out = Vec::with_capacity(self.len());
+        for cp in self.code_points() {
+            if cp <= 0xFFFF {
+                out.push(cp as u16);
+            } else {
+                let c = cp - 0x10000;
+                out.push(0xD800 + (c >> 10) as u16);
+                out.push(0xDC00 + (c & 0x3FF) as u16);
+            }
+        }
+        out
+    }
+
+    /// Convert into well-formed UTF-8, replacing each unpaired surrogate
+    /// with the replacement character `U+FFFD`.
+    ///
+    /// When the tendril is already well-formed UTF-8 the buffer is reused
+    /// without copying.
+    pub fn into_utf8_lossy(self) -> Tendril<fmt::UTF8> {
+        match self.try_reinterpret::<fmt::UTF8>() {
+            Ok(utf8) => utf8,
+            Err(wtf8) => {
+                let mut out: Tendril<fmt::UTF8> = Tendril::new();
+                for cp in wtf8.code_points() {
+                    match char::from_u32(cp) {
+                        Some(c) => out.push_char(c),
+                        None => out.push_char('\u{FFFD}'),
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Iterator over the code points of a `Tendril<fmt::WTF8>`.
+///
+/// Yielded by `Tendril::<fmt::WTF8>::code_points`. Unpaired surrogates are
+/// yielded as code points in `0xD800..=0xDFFF`.
+pub struct CodePoints<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for CodePoints<'a> {
+    type Item = u32;
+
+    #[inline]
+    fn next(&mut self) -> Option<u32> {
+        if self.pos >= self.bytes.len() {
+            return None;
+        }
+        let codept = futf::classify(self.bytes, self.pos)
+            .expect("Tendril<WTF8>::code_points: ill-formed buffer");
+        self.pos += codept.bytes.len();
+        Some(match codept.meaning {
+            Meaning::Whole(c) => c as u32,
+            Meaning::LeadSurrogate(hi) => 0xD800 + hi as u32,
+            Meaning::TrailSurrogate(lo) => 0xDC00 + lo as u32,
+            _ => 0xFFFD,
+        })
+    }
+}
+
 /// Create a `StrTendril` through string formatting.
 ///
 /// Works just like the standard `format!` macro.
@@ -1267,6 +1686,317 @@ impl<'a> From<&'a Tendril<fmt::UTF8>> for String {
 }
 
 
+/// Shared machinery for the `drain` iterators.
+///
+/// The range `[start, tail_start)` is logically removed the moment the
+/// guard is created (by lowering `len` to `start`), so leaking the
+/// iterator truncates rather than corrupting. Dropping the guard shifts
+/// the retained tail `[tail_start, orig_len)` down over the gap.
+struct DrainGuard<F>
+    where F: fmt::Format,
+{
+    ptr: *mut Tendril<F>,
+    start: u32,
+    tail_start: u32,
+    orig_len: u32,
+}
+
+impl<F> Drop for DrainGuard<F>
+    where F: fmt::Format,
+{
+    fn drop(&mut self) {
+        unsafe {
+            let t = &mut *self.ptr;
+            let tail_len = self.orig_len - self.tail_start;
+            if tail_len > 0 {
+                let (buf, _, _) = t.assume_buf_at(self.orig_len);
+                let base = buf.data_ptr();
+                ptr::copy(base.offset(self.tail_start as isize),
+                          base.offset(self.start as isize),
+                          tail_len as usize);
+            }
+            t.len = self.start + tail_len;
+        }
+    }
+}
+
+/// Draining iterator over the removed `char`s of a `StrTendril`.
+///
+/// Returned by `StrTendril::drain`.
+pub struct Drain<'a> {
+    guard: DrainGuard<fmt::UTF8>,
+    cur: u32,
+    end: u32,
+    marker: PhantomData<&'a mut Tendril<fmt::UTF8>>,
+}
+
+impl<'a> Iterator for Drain<'a> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        if self.cur >= self.end {
+            return None;
+        }
+        unsafe {
+            let t = &*self.guard.ptr;
+            let (buf, _, _) = t.assume_buf_at(self.guard.orig_len);
+            let slice = slice::from_raw_parts(buf.data_ptr(), self.guard.orig_len as usize);
+            let s = str::from_utf8_unchecked(&slice[self.cur as usize..self.end as usize]);
+            let c = s.chars().next().unwrap();
+            self.cur += c.len_utf8() as u32;
+            Some(c)
+        }
+    }
+}
+
+/// Draining iterator over the removed `u8`s of a `ByteTendril`.
+///
+/// Returned by `ByteTendril::drain`.
+pub struct ByteDrain<'a> {
+    guard: DrainGuard<fmt::Bytes>,
+    cur: u32,
+    end: u32,
+    marker: PhantomData<&'a mut Tendril<fmt::Bytes>>,
+}
+
+impl<'a> Iterator for ByteDrain<'a> {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<u8> {
+        if self.cur >= self.end {
+            return None;
+        }
+        unsafe {
+            let t = &*self.guard.ptr;
+            let (buf, _, _) = t.assume_buf_at(self.guard.orig_len);
+            let b = *buf.data_ptr().offset(self.cur as isize);
+            self.cur += 1;
+            Some(b)
+        }
+    }
+}
+
+impl Tendril<fmt::UTF8> {
+    /// Remove the byte range `range`, returning an iterator over the
+    /// removed `char`s while retaining the owned capacity.
+    ///
+    /// Returns `ValidationFailed` if the bounds do not fall on character
+    /// boundaries, or `OutOfBounds` if they exceed the length.
+    pub fn drain(&mut self, range: ::core::ops::Range<u32>)
+        -> Result<Drain<'_>, SubtendrilError>
+    {
+        let (start, end, orig_len) = self.prepare_drain(range)?;
+        Ok(Drain {
+            guard: DrainGuard { ptr: self, start, tail_start: end, orig_len },
+            cur: start,
+            end,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl Tendril<fmt::Bytes> {
+    /// Remove the byte range `range`, returning an iterator over the
+    /// removed `u8`s while retaining the owned capacity.
+    pub fn drain(&mut self, range: ::core::ops::Range<u32>)
+        -> Result<ByteDrain<'_>, SubtendrilError>
+    {
+        let (start, end, orig_len) = self.prepare_drain(range)?;
+        Ok(ByteDrain {
+            guard: DrainGuard { ptr: self, start, tail_start: end, orig_len },
+            cur: start,
+            end,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<F> Tendril<F>
+    where F: fmt::Format,
+{
+    /// Validate a drain range, force the buffer owned, and truncate the
+    /// logical length to `start`. Returns `(start, end, orig_len)`.
+    fn prepare_drain(&mut self, range: ::core::ops::Range<u32>)
+        -> Result<(u32, u32, u32), SubtendrilError>
+    {
+        let start = range.start;
+        let end = range.end;
+        let orig_len = self.len32();
+        if start > end || end > orig_len {
+            return Err(SubtendrilError::OutOfBounds);
+        }
+
+        unsafe {
+            let bytes = self.as_byte_slice();
+            if !F::validate_prefix(unsafe_slice(bytes, 0, start as usize))
+                || !F::validate_suffix(unsafe_slice(bytes, end as usize,
+                                                     (orig_len - end) as usize))
+            {
+                return Err(SubtendrilError::ValidationFailed);
+            }
+
+            // Keep capacity; force owned so the gap-closing shift is valid.
+            self.make_owned_with_capacity(orig_len);
+            self.len = start;
+        }
+
+        Ok((start, end, orig_len))
+    }
+
+    /// Like `assume_buf`, but reporting `len` as `real_len` rather than
+    /// the (possibly already-lowered) field value. Only valid on an
+    /// owned, unshared buffer.
+    #[inline]
+    unsafe fn assume_buf_at(&self, real_len: u32) -> (Buf32<Header>, bool, u32) {
+        let header = self.header();
+        (Buf32 {
+            ptr: header,
+            len: real_len,
+            cap: self.aux.get(),
+            marker: PhantomData,
+        }, false, 0)
+    }
+}
+
+/// A `Tendril` that can be moved between threads.
+///
+/// Produced by `Tendril::into_send`. The buffer is made owned and
+/// unshared on construction, so the reference count is touched by a
+/// single owner at the point of conversion; `Header::refcount` itself is
+/// an `AtomicUsize` (relaxed `fetch_add` on `incref`, `fetch_sub` with an
+/// acquire fence before destruction, following the same discipline as
+/// `bytes::Bytes`), so the value stays sound to `Send` even if it is
+/// subsequently `clone()`d and shared again on the receiving thread.
+/// Convert it back with `From`.
+pub struct SendTendril<F>
+    where F: fmt::Format,
+{
+    tendril: Tendril<F>,
+}
+
+// Safe because `into_send` guarantees an owned, unshared buffer, and
+// `Header::refcount` is an `AtomicUsize`, so incref/decref of a buffer
+// shared after crossing threads is race-free.
+unsafe impl<F> Send for SendTendril<F> where F: fmt::Format { }
+
+impl<F> SendTendril<F>
+    where F: fmt::Format,
+{
+    /// Recover an ordinary `Tendril`.
+    #[inline]
+    pub fn into_tendril(self) -> Tendril<F> {
+        self.tendril
+    }
+
+    /// Borrow the underlying `Tendril` without converting.
+    #[inline]
+    pub fn as_tendril(&self) -> &Tendril<F> {
+        &self.tendril
+    }
+}
+
+impl<F> From<SendTendril<F>> for Tendril<F>
+    where F: fmt::Format,
+{
+    #[inline]
+    fn from(s: SendTendril<F>) -> Tendril<F> {
+        s.tendril
+    }
+}
+
+impl<F> Tendril<F>
+    where F: fmt::Format,
+{
+    /// Convert into a `SendTendril` that can cross thread boundaries.
+    ///
+    /// If the buffer is shared, it is first copied into fresh owned
+    /// storage so no reference count is shared between threads.
+    #[inline]
+    pub fn into_send(mut self) -> SendTendril<F> {
+        if self.is_shared() {
+            let len = self.len32();
+            unsafe { self.make_owned_with_capacity(len); }
+        }
+        SendTendril { tendril: self }
+    }
+}
+
+/// `bytes::Buf`/`BufMut` integration, gated on the `bytes` feature.
+///
+/// This lets a `ByteTendril` be filled directly by a socket read or
+/// drained into a writer with no intermediate allocation, reusing the
+/// owned/shared machinery above.
+#[cfg(feature = "bytes")]
+mod bytes_buf {
+    use super::Tendril;
+    use fmt;
+    use std::u32;
+
+    use bytes::{Buf, BufMut};
+    use bytes::buf::UninitSlice;
+
+    /// How much spare capacity `chunk_mut` requests when the tendril is full.
+    const CHUNK: u32 = 64;
+
+    impl Buf for Tendril<fmt::Bytes> {
+        #[inline]
+        fn remaining(&self) -> usize {
+            self.len32() as usize
+        }
+
+        #[inline]
+        fn chunk(&self) -> &[u8] {
+            self.as_byte_slice()
+        }
+
+        #[inline]
+        fn advance(&mut self, cnt: usize) {
+            assert!(cnt <= self.len32() as usize, "Buf::advance past end of ByteTendril");
+            if cnt > 0 {
+                // Shares the backing buffer rather than copying.
+                unsafe { self.unsafe_pop_front(cnt as u32); }
+            }
+        }
+    }
+
+    unsafe impl BufMut for Tendril<fmt::Bytes> {
+        #[inline]
+        fn remaining_mut(&self) -> usize {
+            (u32::MAX - self.len32()) as usize
+        }
+
+        #[inline]
+        unsafe fn advance_mut(&mut self, cnt: usize) {
+            // `chunk_mut` has already made the buffer owned with enough
+            // capacity; just publish the freshly written bytes. The owned
+            // buffer may legitimately be shorter than `MAX_INLINE_LEN` (e.g.
+            // a 3-byte socket read into a previously-empty tendril), so
+            // there's no length invariant to assert here.
+            let new_len = self.len32().checked_add(cnt as u32).expect(::OFLOW);
+            self.len = new_len;
+        }
+
+        #[inline]
+        fn chunk_mut(&mut self) -> &mut UninitSlice {
+            let len = self.len32();
+            unsafe {
+                self.make_owned_with_capacity(len.checked_add(CHUNK).expect(::OFLOW));
+                let (buf, _, _) = self.assume_buf();
+                let spare = buf.cap - len;
+                UninitSlice::from_raw_parts_mut(buf.data_ptr().offset(len as isize), spare as usize)
+            }
+        }
+    }
+}
+
+/// `StrTendril` that can be moved between threads.
+pub type SendStrTendril = SendTendril<fmt::UTF8>;
+
+/// `ByteTendril` that can be moved between threads.
+pub type SendByteTendril = SendTendril<fmt::Bytes>;
+
 #[cfg(all(test, feature = "unstable"))]
 #[path="bench.rs"]
 mod bench;
@@ -1274,6 +2004,7 @@ mod bench;
 #[cfg(test)]
 mod test {
     use super::{Tendril, ByteTendril, StrTendril, ReadExt, SliceExt, Header};
+    use super::SendTendril;
     use fmt;
     use std::iter;
 
@@ -1517,6 +2248,30 @@ mod test {
         assert!(t.try_reinterpret_view::<fmt::UTF8>().is_err());
     }
 
+    #[test]
+    fn wtf8_code_points() {
+        // "a" + lone lead surrogate U+D83D + astral U+1F4A9.
+        let t: Tendril<fmt::WTF8>
+            = Tendril::try_from_byte_slice(b"a\xED\xA0\xBD\xF0\x9F\x92\xA9").unwrap();
+        let cps: Vec<u32> = t.code_points().collect();
+        assert_eq!(vec![0x61, 0xD83D, 0x1F4A9], cps);
+
+        let units = t.to_ill_formed_utf16();
+        assert_eq!(vec![0x61, 0xD83D, 0xD83D, 0xDCA9], units);
+    }
+
+    #[test]
+    fn wtf8_into_utf8_lossy() {
+        // Well-formed input reuses the buffer.
+        let t: Tendril<fmt::WTF8> = b"hi".to_tendril().try_reinterpret().unwrap();
+        assert_eq!("hi", &*t.into_utf8_lossy());
+
+        // Unpaired surrogate becomes U+FFFD.
+        let t: Tendril<fmt::WTF8>
+            = Tendril::try_from_byte_slice(b"a\xED\xA0\xBDb").unwrap();
+        assert_eq!("a\u{FFFD}b", &*t.into_utf8_lossy());
+    }
+
     #[test]
     fn front_char() {
         let mut t = "".to_tendril();
@@ -1643,6 +2398,28 @@ mod test {
             &*t.decode(all::UTF_8, DecoderTrap::Replace).unwrap());
     }
 
+    #[test]
+    fn decode_auto() {
+        use encoding::DecoderTrap;
+
+        let t = b"\xEF\xBB\xBFhi".to_tendril();
+        assert_eq!("hi", &*t.decode_auto(DecoderTrap::Strict).unwrap());
+
+        let t = b"\xFF\xFEh\x00i\x00".to_tendril();
+        assert_eq!("hi", &*t.decode_auto(DecoderTrap::Strict).unwrap());
+
+        let t = b"\xFE\xFF\x00h\x00i".to_tendril();
+        assert_eq!("hi", &*t.decode_auto(DecoderTrap::Strict).unwrap());
+
+        // No BOM falls back to UTF-8.
+        let t = b"plain".to_tendril();
+        assert_eq!("plain", &*t.decode_auto(DecoderTrap::Strict).unwrap());
+
+        // A bare 0xFF is not a truncated UTF-16 BOM.
+        let t = b"\xFF".to_tendril();
+        assert!(t.decode_auto(DecoderTrap::Strict).is_err());
+    }
+
     #[test]
     fn ascii() {
         fn mk(x: &[u8]) -> Tendril<fmt::ASCII> {
@@ -1669,6 +2446,57 @@ mod test {
         assert_eq!(b"x\0", t.as_byte_slice());
     }
 
+    #[test]
+    fn pop_front_while_until() {
+        let mut t = "  foo123".to_tendril();
+        assert_eq!("  ", &*t.pop_front_while(char::is_whitespace));
+        assert_eq!("", &*t.pop_front_while(char::is_whitespace));
+        assert_eq!("foo", &*t.pop_front_until(|c| c.is_ascii_digit()));
+        assert_eq!("123", &*t.pop_front_while(|c| c.is_ascii_digit()));
+        assert_eq!("", &*t);
+    }
+
+    #[test]
+    fn char_runs() {
+        let t = "aaBBc".to_tendril();
+        let runs: Vec<_> = t.char_runs(char::is_uppercase)
+            .map(|(piece, class)| (String::from(&*piece), class))
+            .collect();
+        assert_eq!(vec![
+            ("aa".to_owned(), false),
+            ("BB".to_owned(), true),
+            ("c".to_owned(), false),
+        ], runs);
+    }
+
+    #[test]
+    fn split_char_generic() {
+        let t = "a,b,,c,".to_tendril();
+        let v: Vec<_> = t.split_char(',').map(|s| String::from(&*s)).collect();
+        assert_eq!(vec!["a", "b", "", "c", ""], v);
+
+        // Pieces larger than the inline bound share the parent buffer.
+        let t = "alpha,beta,gamma".to_tendril();
+        for piece in t.split_char(',') {
+            assert!(piece.is_shared_with(&t) || piece.len32() <= 8);
+        }
+    }
+
+    #[test]
+    fn split_char_run_generic() {
+        let t = "ab12cd".to_tendril();
+        let v: Vec<_> = t.split_char_run(|c| c.is_numeric())
+            .map(|s| String::from(&*s)).collect();
+        assert_eq!(vec!["ab", "12", "cd"], v);
+    }
+
+    #[test]
+    fn lines_generic() {
+        let t = "one\ntwo\r\nthree".to_tendril();
+        let v: Vec<_> = t.lines().map(|s| String::from(&*s)).collect();
+        assert_eq!(vec!["one", "two", "three"], v);
+    }
+
     #[test]
     fn latin1() {
         fn mk(x: &[u8]) -> Tendril<fmt::Latin1> {
@@ -1885,6 +2713,63 @@ mod test {
         check(&long);
     }
 
+    #[test]
+    fn read_from() {
+        use std::io::Cursor;
+
+        let mut t: Tendril<fmt::Bytes> = Tendril::new();
+        let n = t.read_from(&mut Cursor::new(b"abcdef"), 4).unwrap();
+        assert_eq!(4, n);
+        assert_eq!(b"abcd", &*t);
+
+        let mut t: Tendril<fmt::Bytes> = Tendril::new();
+        let total = t.read_to_end(&mut Cursor::new(b"hello world")).unwrap();
+        assert_eq!(11, total);
+        assert_eq!(b"hello world", &*t);
+    }
+
+    #[test]
+    fn drain() {
+        let mut t = "hello world".to_tendril();
+        {
+            let drained: String = t.drain(5..11).unwrap().collect();
+            assert_eq!(" world", drained);
+        }
+        assert_eq!("hello", &*t);
+
+        let mut t = b"0123456789".to_tendril();
+        {
+            let drained: Vec<u8> = t.drain(2..5).unwrap().collect();
+            assert_eq!(b"234", &*drained);
+        }
+        assert_eq!(b"0156789", &*t);
+
+        // Leaking the iterator leaves the tendril truncated, not corrupt.
+        let mut t = "abcdefgh".to_tendril();
+        ::std::mem::forget(t.drain(2..4).unwrap());
+        assert_eq!("ab", &*t);
+
+        // Misaligned boundaries are rejected.
+        let mut t = "\u{1f4a9}x".to_tendril();
+        assert!(t.drain(1..4).is_err());
+    }
+
+    #[test]
+    fn into_send() {
+        fn assert_send<T: Send>(_: &T) {}
+
+        // Shared buffers are unshared on the way across.
+        let t = "a rather long shared string".to_tendril();
+        let s = t.clone();
+        assert!(t.is_shared());
+        let sent: SendTendril<_> = t.into_send();
+        assert_send(&sent);
+        let back: StrTendril = sent.into();
+        assert_eq!("a rather long shared string", &*back);
+        assert!(!back.is_shared());
+        assert_eq!("a rather long shared string", &*s);
+    }
+
     #[test]
     fn hash_map_key() {
         use std::collections::HashMap;
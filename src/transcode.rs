@@ -0,0 +1,109 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Incremental streaming transcoders alongside one-shot `decode`/`encode`.
+//!
+//! `StreamDecoder` and `StreamEncoder` transcode a stream fed in chunks,
+//! accumulating the converted output into an internal tendril across
+//! `feed` calls and handing back the whole result from `finish`, so the
+//! memory they hold grows with the output. They wrap
+//! `incremental::IncrementalDecoder`/`TendrilEncoder`, which already carry a
+//! split multi-byte sequence across a `feed` boundary; this module just
+//! layers the accumulate-then-finish shape on top.
+
+use std::borrow::Cow;
+
+use encoding::{EncodingRef, DecoderTrap, EncoderTrap};
+
+use incremental::{IncrementalDecoder, TendrilEncoder};
+use tendril::{ByteTendril, SliceExt, StrTendril, Tendril};
+
+/// Streaming decoder into an internal `StrTendril`.
+pub struct StreamDecoder {
+    inner: IncrementalDecoder,
+    out: StrTendril,
+}
+
+impl StreamDecoder {
+    /// Create a decoder for `encoding`, applying `trap` to malformed bytes.
+    #[inline]
+    pub fn new(encoding: EncodingRef, trap: DecoderTrap) -> StreamDecoder {
+        StreamDecoder {
+            inner: IncrementalDecoder::new(encoding, trap),
+            out: Tendril::new(),
+        }
+    }
+
+    /// Feed a chunk, appending whatever decodes completely to the output.
+    pub fn feed(&mut self, input: &[u8]) {
+        let decoded = self.inner.feed(&input.to_tendril());
+        self.out.push_tendril(&decoded);
+    }
+
+    /// Flush and return the accumulated output, or a decode error for a
+    /// genuinely incomplete/invalid tail.
+    pub fn finish(mut self) -> Result<StrTendril, Cow<'static, str>> {
+        let tail = self.inner.finish().map_err(|e| Cow::Owned(e.to_string()))?;
+        self.out.push_tendril(&tail);
+        Ok(self.out)
+    }
+}
+
+/// Streaming encoder into an internal `ByteTendril`.
+pub struct StreamEncoder {
+    inner: TendrilEncoder,
+    out: ByteTendril,
+}
+
+impl StreamEncoder {
+    /// Create an encoder for `encoding`, applying `trap` to unencodable chars.
+    #[inline]
+    pub fn new(encoding: EncodingRef, trap: EncoderTrap) -> StreamEncoder {
+        StreamEncoder {
+            inner: TendrilEncoder::new(encoding, trap),
+            out: Tendril::new(),
+        }
+    }
+
+    /// Feed a string chunk, appending whatever encodes completely.
+    pub fn feed(&mut self, input: &str) {
+        let encoded = self.inner.feed(&input.to_tendril());
+        self.out.push_tendril(&encoded);
+    }
+
+    /// Flush and return the accumulated output.
+    pub fn finish(mut self) -> Result<ByteTendril, Cow<'static, str>> {
+        let tail = self.inner.finish().map_err(|e| Cow::Owned(e.to_string()))?;
+        self.out.push_tendril(&tail);
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{StreamDecoder, StreamEncoder};
+    use encoding::all as enc;
+    use encoding::{DecoderTrap, EncoderTrap};
+
+    #[test]
+    fn decode_split() {
+        let mut d = StreamDecoder::new(enc::UTF_8, DecoderTrap::Replace);
+        d.feed(b"\xEA\x99");
+        d.feed(b"\xAEz");
+        assert_eq!("\u{a66e}z", &*d.finish().unwrap());
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let mut e = StreamEncoder::new(enc::KOI8_U, EncoderTrap::Replace);
+        e.feed("Эне");
+        e.feed("ргия");
+        let bytes = e.finish().unwrap();
+        let mut d = StreamDecoder::new(enc::KOI8_U, DecoderTrap::Replace);
+        d.feed(&bytes);
+        assert_eq!("Энергия", &*d.finish().unwrap());
+    }
+}
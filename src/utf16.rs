@@ -0,0 +1,128 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Zero-allocation transcoding between UTF-8 and UTF-16 tendrils.
+//!
+//! These stream directly through the backing buffer, never building an
+//! intermediate `Vec<u16>`, so text can cross the UTF-8/UTF-16 boundary
+//! while keeping tendril's inline and refcount machinery.
+
+use fmt;
+use tendril::{ByteTendril, StrTendril, Tendril};
+
+/// A UTF-16 format marker, tagged with its endianness.
+pub trait Utf16Format: fmt::Format {
+    /// Whether code units are laid out big-endian.
+    const BIG_ENDIAN: bool;
+}
+
+impl Utf16Format for fmt::UTF16LE {
+    const BIG_ENDIAN: bool = false;
+}
+
+impl Utf16Format for fmt::UTF16BE {
+    const BIG_ENDIAN: bool = true;
+}
+
+#[inline]
+fn push_unit<E: Utf16Format>(buf: &mut ByteTendril, unit: u16) {
+    let bytes = if E::BIG_ENDIAN {
+        [(unit >> 8) as u8, unit as u8]
+    } else {
+        [unit as u8, (unit >> 8) as u8]
+    };
+    buf.push_slice(&bytes);
+}
+
+impl StrTendril {
+    /// Transcode this UTF-8 tendril into UTF-16 of the requested endianness.
+    pub fn to_utf16<E: Utf16Format>(&self) -> Tendril<E> {
+        let mut out: ByteTendril = Tendril::with_capacity(self.len32() * 2);
+        let mut units = [0u16; 2];
+        for c in self.chars() {
+            for &unit in c.encode_utf16(&mut units).iter() {
+                push_unit::<E>(&mut out, unit);
+            }
+        }
+        unsafe { out.reinterpret_without_validating() }
+    }
+}
+
+impl<E> Tendril<E>
+    where E: Utf16Format,
+{
+    /// Transcode this UTF-16 tendril into UTF-8.
+    ///
+    /// Returns `Err` if the buffer contains an unpaired surrogate,
+    /// which cannot be represented in UTF-8.
+    pub fn to_utf8(&self) -> Result<StrTendril, ()> {
+        let bytes = &**self.as_bytes();
+        let mut out = StrTendril::with_capacity(bytes.len() as u32);
+        let mut i = 0;
+        while i + 1 < bytes.len() {
+            let u = read_unit::<E>(bytes, i);
+            i += 2;
+            let scalar = match u {
+                0xD800..=0xDBFF => {
+                    if i + 1 >= bytes.len() {
+                        return Err(());
+                    }
+                    let lo = read_unit::<E>(bytes, i);
+                    if !(0xDC00..=0xDFFF).contains(&lo) {
+                        return Err(());
+                    }
+                    i += 2;
+                    0x10000 + (((u - 0xD800) as u32) << 10) + ((lo - 0xDC00) as u32)
+                }
+                0xDC00..=0xDFFF => return Err(()),
+                _ => u as u32,
+            };
+            // Every scalar produced here is a valid Unicode scalar value.
+            out.push_char(::std::char::from_u32(scalar).ok_or(())?);
+        }
+        if i != bytes.len() {
+            return Err(());
+        }
+        Ok(out)
+    }
+}
+
+#[inline]
+fn read_unit<E: Utf16Format>(buf: &[u8], i: usize) -> u16 {
+    if E::BIG_ENDIAN {
+        ((buf[i] as u16) << 8) | (buf[i + 1] as u16)
+    } else {
+        ((buf[i + 1] as u16) << 8) | (buf[i] as u16)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use fmt;
+    use tendril::{SliceExt, Tendril};
+
+    #[test]
+    fn round_trip_le() {
+        let t = "héllo \u{1f4a9}".to_tendril();
+        let u16t: Tendril<fmt::UTF16LE> = t.to_utf16();
+        assert_eq!(t, u16t.to_utf8().unwrap());
+    }
+
+    #[test]
+    fn round_trip_be() {
+        let t = "αβγ".to_tendril();
+        let u16t: Tendril<fmt::UTF16BE> = t.to_utf16();
+        assert_eq!(t, u16t.to_utf8().unwrap());
+    }
+
+    #[test]
+    fn rejects_lone_surrogate() {
+        // 0xD800 with no trailing surrogate.
+        let bytes = b"\x00\xD8".to_tendril();
+        let bad: Tendril<fmt::UTF16LE> = unsafe { bytes.reinterpret_without_validating() };
+        assert!(bad.to_utf8().is_err());
+    }
+}
@@ -5,24 +5,24 @@
 // except according to those terms.
 
 use fmt;
-use tendril::{Tendril, Atomicity};
+use tendril::Tendril;
 use utf8;
 
-pub enum Utf8DecodeError<A> where A: Atomicity {
+pub enum Utf8DecodeError {
     Invalid {
-        valid_prefix: Tendril<fmt::UTF8, A>,
-        remaining_input: Tendril<fmt::Bytes, A>,
+        valid_prefix: Tendril<fmt::UTF8>,
+        remaining_input: Tendril<fmt::Bytes>,
     },
     Incomplete {
-        valid_prefix: Tendril<fmt::UTF8, A>,
+        valid_prefix: Tendril<fmt::UTF8>,
         incomplete_suffix: IncompleteUtf8,
     },
 }
 
 pub struct IncompleteUtf8(utf8::Incomplete);
 
-impl<A> Tendril<fmt::Bytes, A> where A: Atomicity {
-    pub fn decode_utf8(mut self) -> Result<Tendril<fmt::UTF8, A>, Utf8DecodeError<A>> {
+impl Tendril<fmt::Bytes> {
+    pub fn decode_utf8(mut self) -> Result<Tendril<fmt::UTF8>, Utf8DecodeError> {
         let unborrowed_result = match utf8::decode(&self) {
             Ok(s) => {
                 debug_assert!(s.as_ptr() == self.as_ptr());
@@ -67,12 +67,136 @@ impl<A> Tendril<fmt::Bytes, A> where A: Atomicity {
             }
         }
     }
+
+    /// Decode into UTF-8, replacing each maximal invalid sequence with
+    /// `U+FFFD`.
+    ///
+    /// Follows the WHATWG "substitution of maximal subpart" rule encoded
+    /// by [`utf8::decode`]'s `invalid_sequence` length. When the whole
+    /// input is valid the result shares the original buffer; a fresh
+    /// buffer is only allocated once a replacement is actually needed,
+    /// mirroring the fast/slow split of `String::from_utf8_lossy`.
+    pub fn decode_utf8_lossy(self) -> Tendril<fmt::UTF8> {
+        // Fast path: a single shared buffer when everything is valid.
+        match utf8::decode(&self) {
+            Ok(_) => return unsafe { self.reinterpret_without_validating() },
+            Err(_) => {}
+        }
+
+        let mut out: Tendril<fmt::UTF8> = Tendril::new();
+        let mut rest: &[u8] = &self;
+        loop {
+            match utf8::decode(rest) {
+                Ok(valid) => {
+                    out.push_slice(valid);
+                    break;
+                }
+                Err(utf8::DecodeError::Invalid { valid_prefix, remaining_input, .. }) => {
+                    out.push_slice(valid_prefix);
+                    out.push_slice(utf8::REPLACEMENT_CHARACTER);
+                    rest = remaining_input;
+                }
+                Err(utf8::DecodeError::Incomplete { valid_prefix, .. }) => {
+                    out.push_slice(valid_prefix);
+                    out.push_slice(utf8::REPLACEMENT_CHARACTER);
+                    break;
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A stateful driver over [`decode_utf8`](struct.Tendril.html#method.decode_utf8)
+/// and [`IncompleteUtf8::try_complete`] for streaming input.
+///
+/// Feed an unbounded sequence of `Tendril<fmt::Bytes>` fragments and
+/// pull out decoded `Tendril<fmt::UTF8>` pieces; a multi-byte sequence
+/// split across a `feed` boundary is carried internally and completed on
+/// the next call. Valid runs are returned as zero-copy subtendrils
+/// sharing the incoming buffer wherever `decode_utf8` allows.
+pub struct IncrementalUtf8Decoder {
+    incomplete: Option<IncompleteUtf8>,
+}
+
+impl Default for IncrementalUtf8Decoder {
+    #[inline]
+    fn default() -> IncrementalUtf8Decoder {
+        IncrementalUtf8Decoder { incomplete: None }
+    }
+}
+
+impl IncrementalUtf8Decoder {
+    #[inline]
+    pub fn new() -> IncrementalUtf8Decoder {
+        IncrementalUtf8Decoder { incomplete: None }
+    }
+
+    pub fn feed(&mut self, chunk: Tendril<fmt::Bytes>)
+                -> Result<Tendril<fmt::UTF8>, Utf8DecodeError> {
+        let mut out: Tendril<fmt::UTF8> = Tendril::new();
+
+        let remainder = match self.incomplete.take() {
+            Some(mut inc) => match inc.try_complete(chunk) {
+                // Still incomplete: the bytes were absorbed into `inc`.
+                None => {
+                    self.incomplete = Some(inc);
+                    return Ok(out);
+                }
+                Some((Ok(s), rest)) => {
+                    out.push_tendril(&s);
+                    rest
+                }
+                Some((Err(()), remaining_input)) => {
+                    return Err(Utf8DecodeError::Invalid {
+                        valid_prefix: out,
+                        remaining_input: remaining_input,
+                    });
+                }
+            },
+            None => chunk,
+        };
+
+        match remainder.decode_utf8() {
+            // With nothing completed from an earlier chunk, hand back the
+            // decoded tendril itself so it keeps sharing the chunk's buffer.
+            Ok(s) => {
+                if out.len32() == 0 {
+                    return Ok(s);
+                }
+                out.push_tendril(&s);
+                Ok(out)
+            }
+            Err(Utf8DecodeError::Incomplete { valid_prefix, incomplete_suffix }) => {
+                self.incomplete = Some(incomplete_suffix);
+                if out.len32() == 0 {
+                    return Ok(valid_prefix);
+                }
+                out.push_tendril(&valid_prefix);
+                Ok(out)
+            }
+            Err(Utf8DecodeError::Invalid { valid_prefix, remaining_input }) => {
+                out.push_tendril(&valid_prefix);
+                Err(Utf8DecodeError::Invalid {
+                    valid_prefix: out,
+                    remaining_input: remaining_input,
+                })
+            }
+        }
+    }
+
+    /// Finish decoding, erroring if a dangling incomplete sequence remains.
+    pub fn finish(self) -> Result<(), ()> {
+        match self.incomplete {
+            None => Ok(()),
+            Some(_) => Err(()),
+        }
+    }
 }
 
 impl IncompleteUtf8 {
-    pub fn try_complete<A>(&mut self, mut input: Tendril<fmt::Bytes, A>)
-                           -> Option<(Result<Tendril<fmt::UTF8, A>, ()>, Tendril<fmt::Bytes, A>)>
-    where A: Atomicity {
+    pub fn try_complete(&mut self, mut input: Tendril<fmt::Bytes>)
+                        -> Option<(Result<Tendril<fmt::UTF8>, ()>, Tendril<fmt::Bytes>)> {
         let result;
         let resume_at;
         match self.0.try_complete(&input) {